@@ -0,0 +1,114 @@
+use std::collections::HashMap;
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use field_block::{Block, Def, FieldName, Len, Val, U64};
+
+fn fixed_layout(c: &mut Criterion) {
+    let block = get_block();
+    let buf = get_buf(&block);
+
+    let mut group = c.benchmark_group("fixed_layout");
+    group.bench_function("to_values", |b| {
+        let mut values = HashMap::new();
+        b.iter(|| {
+            block.to_values(black_box(&buf), &mut values).unwrap();
+        })
+    });
+    group.bench_function("plan_to_values", |b| {
+        let plan = block.compile();
+        let mut values = HashMap::new();
+        b.iter(|| {
+            plan.to_values(black_box(&buf), &mut values).unwrap();
+        })
+    });
+    group.bench_function("plan_to_array", |b| {
+        let plan = block.compile();
+        let mut values: [_; FIELDS] = std::array::from_fn(|_| None);
+        b.iter(|| {
+            plan.to_values(black_box(&buf), &mut values).unwrap();
+        })
+    });
+    group.bench_function("view_get_last", |b| {
+        b.iter(|| {
+            let mut view = block.view(black_box(&buf));
+            view.get(&Name(FIELDS - 1)).unwrap().unwrap();
+        })
+    });
+    group.finish();
+}
+
+fn variable_layout(c: &mut Criterion) {
+    let block = get_variable_block();
+    let buf = get_buf(&block);
+
+    let mut group = c.benchmark_group("variable_layout");
+    group.bench_function("to_values", |b| {
+        let mut values = HashMap::new();
+        b.iter(|| {
+            block.to_values(black_box(&buf), &mut values).unwrap();
+        })
+    });
+    group.bench_function("plan_to_values", |b| {
+        let plan = block.compile();
+        let mut values = HashMap::new();
+        b.iter(|| {
+            plan.to_values(black_box(&buf), &mut values).unwrap();
+        })
+    });
+    group.bench_function("view_get_last", |b| {
+        b.iter(|| {
+            let mut view = block.view(black_box(&buf));
+            view.get(&Name(FIELDS - 1)).unwrap().unwrap();
+        })
+    });
+    group.finish();
+}
+
+const FIELDS: usize = 20;
+
+fn get_block() -> Block<Name> {
+    let mut block = Block::new();
+    for i in 0..FIELDS {
+        let def = match i % 3 {
+            0 => Def::VarInt(U64::Fixed(i as u64)),
+            1 => Def::Bytes(Len::Fixed(4)),
+            _ => Def::FixedBytes(vec![0xba, 0xad, 0xf0, 0x0d]),
+        };
+        block.add_field(Name(i), def);
+    }
+    block
+}
+
+/// Like [`get_block`] but with varint length prefixes on the byte fields
+fn get_variable_block() -> Block<Name> {
+    let mut block = Block::new();
+    for i in 0..FIELDS {
+        let def = match i % 3 {
+            0 => Def::VarInt(U64::Fixed(i as u64)),
+            1 => Def::Bytes(Len::Var),
+            _ => Def::FixedBytes(vec![0xba, 0xad, 0xf0, 0x0d]),
+        };
+        block.add_field(Name(i), def);
+    }
+    block
+}
+
+fn get_buf(block: &Block<Name>) -> Vec<u8> {
+    let bytes = [1, 2, 3, 4];
+    let mut values = HashMap::new();
+    for i in (1..FIELDS).step_by(3) {
+        values.insert(Name(i), Val::Bytes(&bytes));
+    }
+    let mut buf = vec![0; 1024];
+    let end = block.to_bytes(&values, &mut buf).unwrap();
+    buf.truncate(end);
+    buf
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+struct Name(usize);
+
+impl FieldName for Name {}
+
+criterion_group!(benches, fixed_layout, variable_layout);
+criterion_main!(benches);
@@ -0,0 +1,257 @@
+//! Decodes and encodes buffers with a schema file.
+//!
+//! ```text
+//! field_block decode <schema> [input] [--format hex|base64|binary]
+//! field_block encode <schema> [input] [--format hex|base64|binary]
+//! ```
+//!
+//! `decode` reads an encoded buffer and prints its fields as a JSON object.
+//! `encode` reads a JSON object and prints the encoded buffer. The input is
+//! read from stdin if it is missing or `-`. Encoded buffers are hex by default.
+
+use std::{
+    collections::HashMap,
+    io::{Read, Write},
+    process::ExitCode,
+};
+
+use field_block::{Block, BytesFormat, ToBytesError};
+
+mod schema;
+
+/// The largest buffer an encoded block may take
+const MAX_SIZE: usize = 1 << 24;
+
+const USAGE: &str =
+    "usage: field_block <decode|encode> <schema> [input] [--format hex|base64|binary]";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Hex,
+    Base64,
+    Binary,
+}
+
+struct Args {
+    command: String,
+    schema: String,
+    input: Option<String>,
+    format: Format,
+}
+
+fn main() -> ExitCode {
+    let args = match parse_args(std::env::args().skip(1)) {
+        Ok(Some(args)) => args,
+        Ok(None) => {
+            println!("{USAGE}");
+            return ExitCode::SUCCESS;
+        }
+        Err(e) => {
+            eprintln!("error: {e}\n{USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+    match run(&args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Returns `None` if help is requested.
+fn parse_args(args: impl Iterator<Item = String>) -> Result<Option<Args>, String> {
+    let mut positional = Vec::new();
+    let mut format = Format::Hex;
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--format" => {
+                format = match args.next().as_deref() {
+                    Some("hex") => Format::Hex,
+                    Some("base64") => Format::Base64,
+                    Some("binary") => Format::Binary,
+                    Some(x) => return Err(format!("unknown format `{x}`")),
+                    None => return Err("`--format` needs a value".to_string()),
+                }
+            }
+            "-h" | "--help" => return Ok(None),
+            _ => positional.push(arg),
+        }
+    }
+    let mut positional = positional.into_iter();
+    let (command, schema) = match (positional.next(), positional.next()) {
+        (Some(command), Some(schema)) => (command, schema),
+        _ => return Err("missing arguments".to_string()),
+    };
+    if !matches!(command.as_str(), "decode" | "encode") {
+        return Err(format!("unknown command `{command}`"));
+    }
+    let input = positional.next().filter(|x| x != "-");
+    if positional.next().is_some() {
+        return Err("too many arguments".to_string());
+    }
+    Ok(Some(Args {
+        command,
+        schema,
+        input,
+        format,
+    }))
+}
+
+fn run(args: &Args) -> Result<(), String> {
+    let schema = std::fs::read_to_string(&args.schema)
+        .map_err(|e| format!("cannot read schema `{}`: {e}", args.schema))?;
+    let block = schema::parse(&schema).map_err(|e| format!("schema `{}`: {e}", args.schema))?;
+    let input = read_input(args.input.as_deref())?;
+    let output = match args.command.as_str() {
+        "decode" => {
+            let buf = from_text(&input, args.format)?;
+            let mut json = decode(&block, &buf)?;
+            json.push('\n');
+            json.into_bytes()
+        }
+        _ => {
+            let text = String::from_utf8(input).map_err(|_| "input is not UTF-8".to_string())?;
+            let buf = encode(&block, &text)?;
+            to_text(&buf, args.format)
+        }
+    };
+    std::io::stdout()
+        .write_all(&output)
+        .map_err(|e| format!("cannot write output: {e}"))
+}
+
+fn read_input(path: Option<&str>) -> Result<Vec<u8>, String> {
+    match path {
+        Some(path) => std::fs::read(path).map_err(|e| format!("cannot read `{path}`: {e}")),
+        None => {
+            let mut buf = Vec::new();
+            match std::io::stdin().read_to_end(&mut buf) {
+                Ok(_) => Ok(buf),
+                Err(e) => Err(format!("cannot read stdin: {e}")),
+            }
+        }
+    }
+}
+
+fn from_text(input: &[u8], format: Format) -> Result<Vec<u8>, String> {
+    let bytes = match format {
+        Format::Hex => BytesFormat::Hex,
+        Format::Base64 => BytesFormat::Base64,
+        Format::Binary => return Ok(input.to_vec()),
+    };
+    let text = std::str::from_utf8(input).map_err(|_| "input is not UTF-8".to_string())?;
+    let text: String = text.chars().filter(|c| !c.is_whitespace()).collect();
+    match bytes.decode(&text) {
+        Some(buf) => Ok(buf),
+        None => Err(format!("input is not valid {format:?}").to_lowercase()),
+    }
+}
+
+fn to_text(buf: &[u8], format: Format) -> Vec<u8> {
+    match format {
+        Format::Hex => format!("{}\n", BytesFormat::Hex.encode(buf)).into_bytes(),
+        Format::Base64 => format!("{}\n", BytesFormat::Base64.encode(buf)).into_bytes(),
+        Format::Binary => buf.to_vec(),
+    }
+}
+
+/// Returns the fields of `buf` as a JSON object in field order.
+fn decode(block: &Block<String>, buf: &[u8]) -> Result<String, String> {
+    let mut values = HashMap::new();
+    let dump = || block.hex_dump(buf).to_string().trim_end().to_string();
+    let end = match block.to_values(buf, &mut values) {
+        Ok(end) => end,
+        Err(e) => return Err(format!("cannot decode: {e}\n{}", dump())),
+    };
+    if end != buf.len() {
+        return Err(format!("{} trailing bytes\n{}", buf.len() - end, dump()));
+    }
+    let values = block.serialize_values(&values, BytesFormat::Hex);
+    serde_json::to_string_pretty(&values).map_err(|e| format!("cannot write JSON: {e}"))
+}
+
+/// Encodes the JSON object in `text`.
+fn encode(block: &Block<String>, text: &str) -> Result<Vec<u8>, String> {
+    let mut de = serde_json::Deserializer::from_str(text);
+    let owned = block
+        .deserialize_values(&mut de, BytesFormat::Hex)
+        .and_then(|owned| de.end().map(|()| owned))
+        .map_err(|e| format!("invalid JSON: {e}"))?;
+    let values = owned
+        .iter()
+        .map(|(name, value)| (name.clone(), value.as_val()))
+        .collect();
+    let mut buf = vec![0; 4096];
+    loop {
+        match block.to_bytes(&values, &mut buf) {
+            Ok(end) => {
+                buf.truncate(end);
+                return Ok(buf);
+            }
+            Err(ToBytesError::NotEnoughSpace(_)) if buf.len() < MAX_SIZE => {
+                buf.resize(buf.len() * 2, 0);
+            }
+            Err(e) => return Err(format!("cannot encode: {e}")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(x: &str) -> Result<Option<Args>, String> {
+        parse_args(x.split_whitespace().map(String::from))
+    }
+
+    #[test]
+    fn test_args() {
+        let x = args("decode a.schema - --format base64").unwrap().unwrap();
+        assert_eq!(x.command, "decode");
+        assert_eq!(x.schema, "a.schema");
+        assert_eq!(x.input, None);
+        assert_eq!(x.format, Format::Base64);
+        assert!(args("decode").is_err());
+        assert!(args("print a.schema").is_err());
+        assert!(args("encode a.schema in --format oct").is_err());
+        assert!(args("decode --help").unwrap().is_none());
+        assert!(args("-h").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let block = schema::parse(
+            "magic: const cafe\n\
+             id: varint\n\
+             name: str var\n\
+             addr: ipv4\n\
+             crc: checksum crc16\n",
+        )
+        .unwrap();
+        let buf = encode(&block, r#"{"id": 7, "name": "hi", "addr": "10.0.0.1"}"#).unwrap();
+        let json = decode(&block, &buf).unwrap();
+        assert!(json.starts_with("{\n  \"magic\": \"cafe\",\n  \"id\": 7,\n  \"name\": \"hi\""));
+        let again: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(encode(&block, &again.to_string()).unwrap(), buf);
+
+        let e = encode(&block, r#"{"id": 7, "nick": "x"}"#).unwrap_err();
+        assert_eq!(
+            e,
+            "invalid JSON: no such field in the block at line 1 column 16"
+        );
+        let e = encode(&block, r#"{"id": "7"}"#).unwrap_err();
+        assert_eq!(
+            e,
+            "invalid JSON: invalid type: string \"7\", expected an unsigned integer at line 1 column 10"
+        );
+        let e = encode(&block, r#"{"id": 7}"#).unwrap_err();
+        assert_eq!(e, "cannot encode: no value provided for \"name\"");
+        let e = decode(&block, &buf[..4]).unwrap_err();
+        assert!(e.starts_with("cannot decode: not enough data for \"name\"\n"));
+        let e = decode(&block, &[&buf[..], &[0]].concat()).unwrap_err();
+        assert!(e.starts_with("1 trailing bytes\n"));
+    }
+}
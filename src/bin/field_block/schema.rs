@@ -0,0 +1,467 @@
+//! Schema files describing a block.
+//!
+//! The textual form has one field per line as `name: type [arg ...] [option=value ...]`,
+//! with `#` starting a comment. The JSON form is an array of objects with
+//! `name`, `type`, an optional `arg` holding the arguments separated by
+//! spaces, and the options as further keys. The types are:
+//!
+//! - `varint`, or `varint N` for a constant
+//! - `bytes N`, `bytes var` or `bytes remaining` for the last field
+//! - `delimited HEX` for bytes up to and consuming a delimiter
+//! - `const HEX` for constant bytes
+//! - `str nul`, `str var` or `str N` for a string padded with zeros
+//! - `align N`, `padding N` and `reserved N`
+//! - `f32 be|le` and `f64 be|le`
+//! - `ufixed I.F be|le` and `sfixed I.F be|le` for Q-format fixed-point numbers
+//! - `bool`, `ipv4`, `ipv6`, `mac` and `uuid`
+//! - `unix secs|millis|micros|nanos 4|8 be|le` and `ntp` for timestamps
+//! - `bcd N` for `N` bytes of packed BCD and `decimal N` for `N` ASCII digits
+//! - `checksum crc32|crc16|modbus|internet [be|le]` over the preceding fields, big-endian by default
+//!
+//! The options are:
+//!
+//! - `labels=1:Ping,5:Pong` to name the values of an integer
+//! - `scale=X` and `offset=X` for the physical value of an integer
+//! - `range=MIN..MAX` and `oneof=A,B,C` to constrain an integer
+//! - `len=MIN..MAX` to constrain the length of bytes or a string
+//!
+//! TLV sequences and custom codecs cannot be described.
+
+use field_block::{
+    AsciiNum, Bcd, Block, BytesFormat, Checksum, ChecksumAlgo, ChecksumRange, Constraint, Def,
+    Delimiter, Endian, FixedPoint, Labels, Len, Scale, StrLen, TimeUnit, Timestamp, U64,
+};
+use serde_json::Value;
+
+/// A field as written in the schema
+struct Spec {
+    name: String,
+    def: Def,
+    options: Vec<(String, String)>,
+}
+
+pub fn parse(text: &str) -> Result<Block<String>, String> {
+    let specs = match text.trim_start().starts_with('[') {
+        true => parse_json(text)?,
+        false => parse_text(text)?,
+    };
+    for (i, spec) in specs.iter().enumerate() {
+        if specs[..i].iter().any(|x| x.name == spec.name) {
+            return Err(format!("duplicate field `{}`", spec.name));
+        }
+        if matches!(spec.def, Def::Bytes(Len::Remaining)) && i + 1 != specs.len() {
+            return Err(format!("`{}` takes the rest and must be last", spec.name));
+        }
+    }
+    let mut block = Block::new();
+    for spec in specs {
+        let field = block.add_field(spec.name.clone(), spec.def);
+        for (key, value) in &spec.options {
+            let context = |e| format!("field `{}`: {e}", spec.name);
+            match key.as_str() {
+                "labels" | "scale" | "offset" | "range" | "oneof" if !field.def().is_int() => {
+                    return Err(context(format!("`{key}` needs an integer type")));
+                }
+                "len"
+                    if !matches!(
+                        field.def(),
+                        Def::Bytes(_) | Def::FixedBytes(_) | Def::Str(_)
+                    ) =>
+                {
+                    return Err(context("`len` needs a bytes or string type".to_string()));
+                }
+                "labels" => {
+                    field.set_labels(parse_labels(value).map_err(context)?);
+                }
+                "scale" | "offset" => {
+                    let x = parse_float(value).map_err(context)?;
+                    let mut scale = field.scale().copied().unwrap_or(Scale::new(1.0, 0.0));
+                    match key.as_str() {
+                        "scale" => scale.scale = x,
+                        _ => scale.offset = x,
+                    }
+                    field.set_scale(scale);
+                }
+                "range" => {
+                    let (min, max) = parse_range(value, parse_int).map_err(context)?;
+                    field.add_constraint(Constraint::Range(min..=max));
+                }
+                "oneof" => {
+                    let set = value.split(',').map(parse_int).collect::<Result<_, _>>();
+                    field.add_constraint(Constraint::OneOf(set.map_err(context)?));
+                }
+                "len" => {
+                    let (min, max) = parse_range(value, parse_len).map_err(context)?;
+                    field.add_constraint(Constraint::Len(min..=max));
+                }
+                _ => return Err(context(format!("unknown option `{key}`"))),
+            }
+        }
+    }
+    Ok(block)
+}
+
+fn parse_text(text: &str) -> Result<Vec<Spec>, String> {
+    let mut specs = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let line = line.split('#').next().unwrap().trim();
+        if line.is_empty() {
+            continue;
+        }
+        let (name, spec) = match line.split_once(':') {
+            Some(x) => x,
+            None => return Err(format!("line {}: expected `name: type`", i + 1)),
+        };
+        let mut words = spec.split_whitespace();
+        let ty = words.next().unwrap_or("");
+        let mut args = Vec::new();
+        let mut options = Vec::new();
+        for word in words {
+            match word.split_once('=') {
+                Some((key, value)) => options.push((key.to_string(), value.to_string())),
+                None => args.push(word),
+            }
+        }
+        let def = parse_def(ty, &args).map_err(|e| format!("line {}: {e}", i + 1))?;
+        specs.push(Spec {
+            name: name.trim().to_string(),
+            def,
+            options,
+        });
+    }
+    Ok(specs)
+}
+
+fn parse_json(text: &str) -> Result<Vec<Spec>, String> {
+    let value: Value = serde_json::from_str(text).map_err(|e| format!("invalid JSON: {e}"))?;
+    let entries = match value.as_array() {
+        Some(x) => x,
+        None => return Err("expected an array of fields".to_string()),
+    };
+    let mut specs = Vec::new();
+    for (i, entry) in entries.iter().enumerate() {
+        let (name, ty) = match (entry["name"].as_str(), entry["type"].as_str()) {
+            (Some(name), Some(ty)) => (name, ty),
+            _ => return Err(format!("field {i}: expected `name` and `type` strings")),
+        };
+        let mut arg = None;
+        let mut options = Vec::new();
+        for (key, value) in entry.as_object().unwrap() {
+            let value = match value {
+                Value::String(x) => x.clone(),
+                Value::Number(x) => x.to_string(),
+                _ => return Err(format!("field {i}: `{key}` must be a string or number")),
+            };
+            match key.as_str() {
+                "name" | "type" => (),
+                "arg" => arg = Some(value),
+                _ => options.push((key.clone(), value)),
+            }
+        }
+        let args: Vec<_> = arg.iter().flat_map(|x| x.split_whitespace()).collect();
+        let def = parse_def(ty, &args).map_err(|e| format!("field {i}: {e}"))?;
+        specs.push(Spec {
+            name: name.to_string(),
+            def,
+            options,
+        });
+    }
+    Ok(specs)
+}
+
+fn parse_def(ty: &str, args: &[&str]) -> Result<Def, String> {
+    let def = match (ty, args) {
+        ("varint", []) => Def::VarInt(U64::Var),
+        ("varint", [x]) => Def::VarInt(U64::Fixed(parse_int(x)?)),
+        ("bytes", ["var"]) => Def::Bytes(Len::Var),
+        ("bytes", ["remaining"]) => Def::Bytes(Len::Remaining),
+        ("bytes", [x]) => Def::Bytes(Len::Fixed(parse_len(x)?)),
+        ("delimited", [x]) => match parse_hex(x)? {
+            x if x.is_empty() => return Err("the delimiter cannot be empty".to_string()),
+            x => Def::Bytes(Len::Delimited(Delimiter::new(x))),
+        },
+        ("const", [x]) => Def::FixedBytes(parse_hex(x)?),
+        ("str", ["nul"]) => Def::Str(StrLen::Nul),
+        ("str", ["var"]) => Def::Str(StrLen::Var),
+        ("str", [x]) => Def::Str(StrLen::Padded {
+            len: parse_len(x)?,
+            pad: 0,
+        }),
+        ("align", [x]) => match parse_len(x)? {
+            0 => return Err("cannot align to 0 bytes".to_string()),
+            to => Def::Align { to, fill: 0 },
+        },
+        ("padding", [x]) => Def::Padding {
+            len: parse_len(x)?,
+            fill: 0,
+        },
+        ("reserved", [x]) => Def::Reserved {
+            len: parse_len(x)?,
+            check: true,
+        },
+        ("f32", [x]) => Def::F32(parse_endian(x)?),
+        ("f64", [x]) => Def::F64(parse_endian(x)?),
+        ("ufixed" | "sfixed", [q, endian]) => {
+            let bits = q.split_once('.').and_then(|(int, frac)| {
+                Some((int.parse::<u32>().ok()?, frac.parse::<u32>().ok()?))
+            });
+            let (int_bits, frac_bits) = match bits {
+                Some((int, frac)) if matches!(int.checked_add(frac), Some(8 | 16 | 32 | 64)) => {
+                    (int, frac)
+                }
+                _ => {
+                    return Err(format!(
+                        "invalid Q format `{q}`, expected 8, 16, 32 or 64 bits as `I.F`"
+                    ))
+                }
+            };
+            Def::FixedPoint(FixedPoint {
+                int_bits,
+                frac_bits,
+                signed: ty == "sfixed",
+                endian: parse_endian(endian)?,
+            })
+        }
+        ("bool", []) => Def::Bool,
+        ("ipv4", []) => Def::Ipv4,
+        ("ipv6", []) => Def::Ipv6,
+        ("mac", []) => Def::Mac,
+        ("uuid", []) => Def::Uuid,
+        ("unix", [unit, size, endian]) => {
+            let unit = match *unit {
+                "secs" => TimeUnit::Secs,
+                "millis" => TimeUnit::Millis,
+                "micros" => TimeUnit::Micros,
+                "nanos" => TimeUnit::Nanos,
+                _ => return Err(format!("unknown time unit `{unit}`")),
+            };
+            let size = match *size {
+                "4" => 4,
+                "8" => 8,
+                _ => return Err(format!("invalid size `{size}`, expected 4 or 8")),
+            };
+            Def::Timestamp(Timestamp::Unix {
+                unit,
+                size,
+                endian: parse_endian(endian)?,
+            })
+        }
+        ("ntp", []) => Def::Timestamp(Timestamp::Ntp),
+        ("bcd", [x]) => Def::Bcd(Bcd::Packed { len: parse_len(x)? }),
+        ("decimal", [x]) => Def::AsciiNum(AsciiNum {
+            len: parse_len(x)?,
+            radix: 10,
+            pad: b'0',
+        }),
+        ("checksum", [x] | [x, _]) => {
+            let algo = match *x {
+                "crc32" => ChecksumAlgo::Crc32,
+                "crc16" => ChecksumAlgo::Crc16Ccitt,
+                "modbus" => ChecksumAlgo::Crc16Modbus,
+                "internet" => ChecksumAlgo::Internet,
+                _ => return Err(format!("unknown checksum `{x}`")),
+            };
+            Def::Checksum(Checksum {
+                algo,
+                range: ChecksumRange::Preceding,
+                endian: match args.get(1) {
+                    Some(x) => parse_endian(x)?,
+                    None => Endian::Big,
+                },
+            })
+        }
+        (
+            "varint" | "bytes" | "delimited" | "const" | "str" | "align" | "padding" | "reserved"
+            | "f32" | "f64" | "ufixed" | "sfixed" | "bool" | "ipv4" | "ipv6" | "mac" | "uuid"
+            | "unix" | "ntp" | "bcd" | "decimal" | "checksum",
+            _,
+        ) => return Err(format!("wrong arguments for `{ty}`")),
+        _ => return Err(format!("unknown type `{ty}`")),
+    };
+    Ok(def)
+}
+
+fn parse_int(x: &str) -> Result<u64, String> {
+    let y = match x.strip_prefix("0x") {
+        Some(hex) => u64::from_str_radix(hex, 16),
+        None => x.parse(),
+    };
+    y.map_err(|_| format!("invalid integer `{x}`"))
+}
+
+fn parse_len(x: &str) -> Result<usize, String> {
+    x.parse().map_err(|_| format!("invalid length `{x}`"))
+}
+
+fn parse_float(x: &str) -> Result<f64, String> {
+    match x.parse::<f64>() {
+        Ok(y) if y.is_finite() => Ok(y),
+        _ => Err(format!("invalid number `{x}`")),
+    }
+}
+
+fn parse_hex(x: &str) -> Result<Vec<u8>, String> {
+    BytesFormat::Hex
+        .decode(x)
+        .ok_or_else(|| format!("invalid hex `{x}`"))
+}
+
+fn parse_endian(x: &str) -> Result<Endian, String> {
+    match x {
+        "be" => Ok(Endian::Big),
+        "le" => Ok(Endian::Little),
+        _ => Err(format!("invalid byte order `{x}`, expected `be` or `le`")),
+    }
+}
+
+/// Parses `MIN..MAX` with both ends included.
+fn parse_range<T>(x: &str, parse: fn(&str) -> Result<T, String>) -> Result<(T, T), String> {
+    match x.split_once("..") {
+        Some((min, max)) => Ok((parse(min)?, parse(max)?)),
+        None => Err(format!("invalid range `{x}`, expected `MIN..MAX`")),
+    }
+}
+
+fn parse_labels(x: &str) -> Result<Labels, String> {
+    let mut entries: Vec<(u64, String)> = Vec::new();
+    for entry in x.split(',') {
+        let (value, label) = match entry.split_once(':') {
+            Some((value, label)) => (parse_int(value)?, label),
+            None => return Err(format!("invalid label `{entry}`, expected `VALUE:LABEL`")),
+        };
+        if entries.iter().any(|(x, y)| *x == value || *y == label) {
+            return Err(format!("duplicate label `{entry}`"));
+        }
+        entries.push((value, label.to_string()));
+    }
+    Ok(Labels::new(entries))
+}
+
+#[cfg(test)]
+mod tests {
+    use field_block::Val;
+
+    use super::*;
+
+    #[test]
+    fn test_text() {
+        let block = parse(
+            "# header\n\
+             magic: varint 0xdeadbeef\n\
+             \n\
+             name: str var  # UTF-8\n\
+             crc: checksum crc32\n",
+        )
+        .unwrap();
+        let mut buf = [0; 64];
+        let values = [("name".to_string(), Val::Str("hi"))].into_iter().collect();
+        let end = block.to_bytes(&values, &mut buf).unwrap();
+        assert_eq!(end, 8 + 3 + 4);
+
+        let e = parse("magic varint").err().unwrap();
+        assert_eq!(e, "line 1: expected `name: type`");
+        let e = parse("a: bytes\n").err().unwrap();
+        assert_eq!(e, "line 1: wrong arguments for `bytes`");
+        let e = parse("a: u16 be\n").err().unwrap();
+        assert_eq!(e, "line 1: unknown type `u16`");
+        let e = parse("a: bool\nb: varint\na: varint\n").err().unwrap();
+        assert_eq!(e, "duplicate field `a`");
+        let e = parse("a: bytes remaining\nb: bool\n").err().unwrap();
+        assert_eq!(e, "`a` takes the rest and must be last");
+    }
+
+    #[test]
+    fn test_types() {
+        let block = parse(
+            "line: delimited 0d0a\n\
+             pad: align 4\n\
+             temp: sfixed 8.8 be\n\
+             at: unix millis 8 le\n\
+             sent: ntp\n",
+        )
+        .unwrap();
+        let sizes: Vec<_> = block
+            .fields()
+            .iter()
+            .map(|field| field.def().fixed_size())
+            .collect();
+        assert_eq!(sizes, [None, None, Some(2), Some(8), Some(8)]);
+
+        let block = parse("a: bytes 2\nb: checksum modbus le").unwrap();
+        let values = [("a".to_string(), Val::Bytes(b"12"))].into_iter().collect();
+        let mut buf = [0; 4];
+        block.to_bytes(&values, &mut buf).unwrap();
+        let crc = ChecksumAlgo::Crc16Modbus.compute(b"12".iter().copied()) as u16;
+        assert_eq!(buf[2..], crc.to_le_bytes());
+
+        let e = parse("a: align 0").err().unwrap();
+        assert_eq!(e, "line 1: cannot align to 0 bytes");
+        let e = parse("a: ufixed 4.5 be").err().unwrap();
+        assert!(e.starts_with("line 1: invalid Q format `4.5`"));
+        let e = parse("a: unix secs 2 be").err().unwrap();
+        assert_eq!(e, "line 1: invalid size `2`, expected 4 or 8");
+    }
+
+    #[test]
+    fn test_options() {
+        let block = parse(
+            "kind: varint labels=1:Ping,5:Pong oneof=1,5\n\
+             temp: varint scale=0.5 offset=-40 range=0..200\n\
+             name: str var len=1..4\n",
+        )
+        .unwrap();
+        let kind = block.field(&"kind".to_string()).unwrap();
+        assert_eq!(kind.labels().unwrap().label(5), Some("Pong"));
+        assert_eq!(kind.constraints().len(), 1);
+        let temp = block.field(&"temp".to_string()).unwrap();
+        assert_eq!(temp.scale().unwrap().to_physical(100), 10.0);
+
+        let mut buf = [0; 64];
+        let values = [
+            ("kind".to_string(), Val::Str("Pong")),
+            ("temp".to_string(), Val::VarInt(201)),
+            ("name".to_string(), Val::Str("hi")),
+        ]
+        .into_iter()
+        .collect();
+        let e = block.to_bytes(&values, &mut buf).unwrap_err();
+        assert!(matches!(
+            e,
+            field_block::ToBytesError::ConstraintViolated { ref name, .. } if name == "temp"
+        ));
+
+        let e = parse("a: bool labels=1:On").err().unwrap();
+        assert_eq!(e, "field `a`: `labels` needs an integer type");
+        let e = parse("a: varint labels=1:On,2:On").err().unwrap();
+        assert_eq!(e, "field `a`: duplicate label `2:On`");
+        let e = parse("a: varint color=red").err().unwrap();
+        assert_eq!(e, "field `a`: unknown option `color`");
+    }
+
+    #[test]
+    fn test_json() {
+        let block = parse(
+            r#"[
+                {"name": "len", "type": "bytes", "arg": 2},
+                {"name": "ok", "type": "bool"},
+                {"name": "at", "type": "unix", "arg": "secs 4 be", "scale": 2}
+            ]"#,
+        );
+        let e = block.err().unwrap();
+        assert_eq!(e, "field `at`: `scale` needs an integer type");
+
+        let block = parse(
+            r#"[{"name": "len", "type": "bytes", "arg": 2}, {"name": "ok", "type": "bool"},
+                {"name": "n", "type": "varint", "range": "1..3"}]"#,
+        )
+        .unwrap();
+        let mut values = std::collections::HashMap::new();
+        block.to_values(&[1, 2, 1, 2], &mut values).unwrap();
+        assert_eq!(values.len(), 3);
+
+        let e = parse(r#"[{"name": "a"}]"#).err().unwrap();
+        assert_eq!(e, "field 0: expected `name` and `type` strings");
+        let e = parse("[").err().unwrap();
+        assert!(e.starts_with("invalid JSON"));
+    }
+}
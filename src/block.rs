@@ -1,73 +1,410 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, hash::Hash};
 
 use octets::{Octets, OctetsMut};
 
-use crate::{Def, Field, FieldName, ToBytesError, ToValuesError, Val, ValInfo};
+use crate::{
+    BlockView, ChecksumRange, DecodePlan, Def, Deferred, Field, FieldName, HexDump, Len, Limits,
+    Span, ToBytesError, ToValuesError, Val, ValueSink,
+};
 
 pub struct Block<F>
 where
     F: FieldName,
 {
     fields: Vec<Field<F>>,
+    limits: Limits,
+    /// The position of a [`Len::RemainingMinus`] field whose trailer does not take exactly its bytes
+    open_trailer: Option<usize>,
 }
 
 impl<F> Block<F>
 where
     F: FieldName,
 {
-    fn check_rep(&self) {}
+    fn check_rep(&self) {
+        for (i, field) in self.fields.iter().enumerate() {
+            field.def().check_rep();
+            let rest = &self.fields[i + 1..];
+            match field.def() {
+                Def::Bytes(Len::Remaining) => assert!(rest.is_empty()),
+                Def::Tlv(x) if x.prefix().is_none() => assert!(rest.is_empty()),
+                Def::Bytes(Len::RemainingMinus(n)) => {
+                    let size = trailer_size(rest);
+                    assert!(size.is_some_and(|size| size <= *n));
+                }
+                Def::Checksum(x) => {
+                    if let ChecksumRange::Last(n) = x.range {
+                        assert!(n <= i);
+                    }
+                    // each checksum is computed after the ones it covers
+                    for (j, other) in self.fields.iter().enumerate() {
+                        if let Def::Checksum(y) = other.def() {
+                            assert!(!(j != i && x.covers(i, j) && y.covers(j, i)));
+                        }
+                    }
+                }
+                _ => (),
+            }
+        }
+    }
+
+    /// Fails with the [`Len::RemainingMinus`] field if the fields after it do not take exactly the bytes it leaves.
+    ///
+    /// Checked on use rather than in `check_rep`, as these fields are added
+    /// after the field they follow.
+    pub(crate) fn check_trailer(&self) -> Result<(), ToValuesError<F>> {
+        match self.open_trailer {
+            Some(i) => Err(ToValuesError::InvalidValue(self.fields[i].name().clone())),
+            None => Ok(()),
+        }
+    }
 
     #[must_use]
     pub fn new() -> Self {
-        let self_ = Block { fields: Vec::new() };
+        let self_ = Block {
+            fields: Vec::new(),
+            limits: Limits::default(),
+            open_trailer: None,
+        };
         self_.check_rep();
         self_
     }
 
-    pub fn add_field(&mut self, name: F, def: Def) {
+    pub fn add_field(&mut self, name: F, def: Def) -> &mut Field<F> {
         self.fields.push(Field::new(name, def));
         self.check_rep();
+        self.open_trailer = self.fields.iter().enumerate().position(|(i, field)| {
+            matches!(field.def(), Def::Bytes(Len::RemainingMinus(n))
+                if trailer_size(&self.fields[i + 1..]) != Some(*n))
+        });
+        self.fields.last_mut().unwrap()
+    }
+
+    #[must_use]
+    pub fn limits(&self) -> &Limits {
+        &self.limits
+    }
+
+    pub fn set_limits(&mut self, limits: Limits) {
+        self.limits = limits;
+        self.check_rep();
     }
 
-    pub fn to_bytes(
+    /// Returns the fields in encoding order.
+    #[must_use]
+    pub fn fields(&self) -> &[Field<F>] {
+        &self.fields
+    }
+
+    #[must_use]
+    pub fn field(&self, name: &F) -> Option<&Field<F>> {
+        self.fields.iter().find(|field| field.name() == name)
+    }
+
+    pub(crate) fn position(&self, name: &F) -> Option<usize> {
+        self.fields.iter().position(|field| field.name() == name)
+    }
+
+    /// Returns the start offset of each field in order, as far as it does not depend on the values.
+    ///
+    /// Offsets are `None` from the first field after one whose size depends
+    /// on its value.
+    pub fn fixed_offsets(&self) -> impl Iterator<Item = Option<usize>> + '_ {
+        let mut pos = Some(0);
+        self.fields.iter().map(move |field| {
+            let start = pos;
+            pos = pos.and_then(|pos| Some(pos + fixed_size_at(field.def(), pos)?));
+            start
+        })
+    }
+
+    /// Returns the start offset of the field named `name` if it does not depend on the values.
+    #[must_use]
+    pub fn fixed_offset(&self, name: &F) -> Option<usize> {
+        self.fixed_offsets().nth(self.position(name)?)?
+    }
+
+    /// Returns the encoded size of the block if it does not depend on the values.
+    #[must_use]
+    pub fn fixed_size(&self) -> Option<usize> {
+        let last = match self.fields.last() {
+            Some(last) => last,
+            None => return Some(0),
+        };
+        let pos = self.fixed_offsets().last().unwrap()?;
+        Some(pos + fixed_size_at(last.def(), pos)?)
+    }
+
+    pub fn to_bytes(&self, values: &HashMap<F, Val>, b: &mut [u8]) -> Result<usize, ToBytesError<F>>
+    where
+        F: Hash,
+    {
+        self.to_bytes_(|field| values.get(field.name()), b, |_, _| {})
+    }
+
+    /// Encodes with `values` in the order of the fields that have values.
+    ///
+    /// Missing trailing values are treated as not provided.
+    pub(crate) fn to_bytes_positional(
         &self,
-        values: &HashMap<F, Val>,
+        values: &[Val],
         b: &mut [u8],
     ) -> Result<usize, ToBytesError<F>> {
-        let mut b = OctetsMut::with_slice(b);
-        return self.to_bytes_(values, &mut b);
+        let mut values = values.iter();
+        self.to_bytes_(
+            |field| match field.def().has_value() {
+                true => values.next(),
+                false => None,
+            },
+            b,
+            |_, _| {},
+        )
     }
 
-    fn to_bytes_(
+    /// Encodes like [`Block::to_bytes`] and records where each field was written in `spans`.
+    pub fn to_bytes_with_spans(
         &self,
         values: &HashMap<F, Val>,
-        b: &mut OctetsMut,
+        b: &mut [u8],
+        spans: &mut HashMap<F, Span>,
+    ) -> Result<usize, ToBytesError<F>>
+    where
+        F: Hash,
+    {
+        self.to_bytes_(
+            |field| values.get(field.name()),
+            b,
+            |name, span| {
+                spans.insert(name.clone(), span);
+            },
+        )
+    }
+
+    fn to_bytes_<'a, 'b: 'a>(
+        &self,
+        mut value: impl FnMut(&Field<F>) -> Option<&'a Val<'b>>,
+        b: &mut [u8],
+        mut on_written: impl FnMut(&F, Span),
     ) -> Result<usize, ToBytesError<F>> {
+        if let Some(i) = self.open_trailer {
+            return Err(ToBytesError::InvalidValue(self.fields[i].name().clone()));
+        }
+        let has_checksums = self.has_checksums();
+        let mut spans = Vec::new();
+        let mut o = OctetsMut::with_slice(b);
         for field in self.fields.iter() {
-            let value = values.get(field.name());
-            field.to_bytes(value, b)?;
+            let value = value(field);
+            let pos = o.off();
+            field.to_bytes(value, &mut o)?;
+            let span = Span {
+                pos,
+                len: o.off() - pos,
+            };
+            if has_checksums {
+                spans.push(span);
+            }
+            on_written(field.name(), span);
         }
-        Ok(b.off())
+        let end = o.off();
+        if has_checksums {
+            self.put_checksums(&mut b[..end], &spans);
+        }
+        Ok(end)
+    }
+
+    /// Fails if the block would take more than [`Limits::max_size`] with `field` ending at `end`.
+    pub(crate) fn check_size(&self, field: &Field<F>, end: usize) -> Result<(), ToValuesError<F>> {
+        match self.limits.max_size {
+            Some(max_size) if end > max_size => {
+                Err(ToValuesError::LimitExceeded(field.name().clone()))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    pub(crate) fn has_checksums(&self) -> bool {
+        self.fields
+            .iter()
+            .any(|field| matches!(field.def(), Def::Checksum(_)))
     }
 
-    pub fn to_values<'buf>(
+    /// Overwrites the placeholders of the checksum fields in the encoded block `b`.
+    ///
+    /// Checksums over the whole block go last, as they may cover the others.
+    /// The others cover only fields before them, so field order suits them.
+    fn put_checksums(&self, b: &mut [u8], spans: &[Span]) {
+        for whole in [false, true] {
+            for (i, field) in self.fields.iter().enumerate() {
+                match field.def() {
+                    Def::Checksum(x) if matches!(x.range, ChecksumRange::All) == whole => {
+                        let value = x.compute(b, i, spans);
+                        x.endian.write(value, &mut b[spans[i].range()]);
+                    }
+                    _ => (),
+                }
+            }
+        }
+    }
+
+    /// Verifies the checksum fields of the decoded block `b`.
+    pub(crate) fn check_checksums(&self, b: &[u8], spans: &[Span]) -> Result<(), ToValuesError<F>> {
+        for (i, field) in self.fields.iter().enumerate() {
+            if let Def::Checksum(x) = field.def() {
+                let expected = x.compute(b, i, spans);
+                let actual = x.endian.read(&b[spans[i].range()]);
+                if expected != actual {
+                    return Err(ToValuesError::ChecksumMismatch {
+                        name: field.name().clone(),
+                        expected,
+                        actual,
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn to_values<'buf, V>(
         &self,
         b: &'buf [u8],
-        values: &mut HashMap<F, ValInfo<'buf>>,
-    ) -> Result<usize, ToValuesError<F>> {
+        values: &mut V,
+    ) -> Result<usize, ToValuesError<F>>
+    where
+        V: ValueSink<'buf, F>,
+    {
         let mut b = Octets::with_slice(b);
-        return self.to_values_(&mut b, values);
+        self.to_values_(&mut b, values, &self.limits)
     }
 
-    fn to_values_<'buf>(
+    pub(crate) fn to_values_<'buf, V>(
         &self,
         b: &mut Octets<'buf>,
-        values: &mut HashMap<F, ValInfo<'buf>>,
-    ) -> Result<usize, ToValuesError<F>> {
-        for field in self.fields.iter() {
-            let value = field.to_value(b)?;
-            values.insert(field.name().clone(), value);
+        values: &mut V,
+        limits: &Limits,
+    ) -> Result<usize, ToValuesError<F>>
+    where
+        V: ValueSink<'buf, F>,
+    {
+        self.check_trailer()?;
+        values.prepare(self.fields.len());
+        if !self.has_checksums() {
+            return self.decode_fields(b, values, limits, None);
+        }
+        // Values are only passed on once the checksums are verified
+        let mut deferred = Deferred::default();
+        let mut spans = Vec::new();
+        let end = self.decode_fields(b, &mut deferred, limits, Some(&mut spans))?;
+        self.check_checksums(&b.buf()[..end], &spans)?;
+        deferred.flush(&self.fields, values);
+        Ok(end)
+    }
+
+    /// Decodes the fields in order, recording their spans in `spans` if given.
+    fn decode_fields<'buf, V>(
+        &self,
+        b: &mut Octets<'buf>,
+        values: &mut V,
+        limits: &Limits,
+        mut spans: Option<&mut Vec<Span>>,
+    ) -> Result<usize, ToValuesError<F>>
+    where
+        V: ValueSink<'buf, F>,
+    {
+        for (i, field) in self.fields.iter().enumerate() {
+            let value = field.to_value_(b, limits)?;
+            self.check_size(field, b.off())?;
+            if let Some(spans) = spans.as_deref_mut() {
+                spans.push(value.span);
+            }
+            if field.def().has_value() {
+                values.insert(i, field.name(), value);
+            }
+        }
+        Ok(b.off())
+    }
+
+    /// Decodes only the fields named in `names`.
+    ///
+    /// Other fields are skipped over without being decoded or validated, and
+    /// checksums are not verified as they cover fields that are skipped.
+    /// Decoding stops right after the last wanted field, and the offset it
+    /// stopped at is returned.
+    pub fn to_selected_values<'buf, V>(
+        &self,
+        b: &'buf [u8],
+        names: &[F],
+        values: &mut V,
+    ) -> Result<usize, ToValuesError<F>>
+    where
+        V: ValueSink<'buf, F>,
+    {
+        self.check_trailer()?;
+        values.prepare(self.fields.len());
+        let mut b = Octets::with_slice(b);
+        let mut remaining = self
+            .fields
+            .iter()
+            .filter(|field| names.contains(field.name()))
+            .count();
+        for (i, field) in self.fields.iter().enumerate() {
+            if remaining == 0 {
+                break;
+            }
+            if !names.contains(field.name()) {
+                field.skip_(&mut b, &self.limits)?;
+                self.check_size(field, b.off())?;
+                continue;
+            }
+            let value = field.to_value_(&mut b, &self.limits)?;
+            self.check_size(field, b.off())?;
+            if field.def().has_value() {
+                values.insert(i, field.name(), value);
+            }
+            remaining -= 1;
         }
         Ok(b.off())
     }
+
+    /// Compiles the block into a plan with precomputed offsets for its fixed-size prefix.
+    #[must_use]
+    pub fn compile(&self) -> DecodePlan<'_, F> {
+        DecodePlan::new(self)
+    }
+
+    /// Returns a view that decodes fields of `b` only when they are asked for.
+    #[must_use]
+    pub fn view<'buf>(&self, b: &'buf [u8]) -> BlockView<'_, 'buf, F> {
+        BlockView::new(self, b)
+    }
+
+    /// Returns an annotated hex dump of `b` for display.
+    #[must_use]
+    pub fn hex_dump<'buf>(&self, b: &'buf [u8]) -> HexDump<'_, 'buf, F> {
+        HexDump::new(self, b)
+    }
+}
+
+/// Returns the total size of `fields` if it does not depend on the values.
+fn trailer_size<F>(fields: &[Field<F>]) -> Option<usize>
+where
+    F: FieldName,
+{
+    fields.iter().map(|field| field.def().fixed_size()).sum()
+}
+
+/// Returns the size of a field with `def` starting at `pos` if it does not depend on the value.
+pub(crate) fn fixed_size_at(def: &Def, pos: usize) -> Option<usize> {
+    match def {
+        Def::Align { to, .. } => Some((to - pos % to) % to),
+        _ => def.fixed_size(),
+    }
+}
+
+impl<F> Default for Block<F>
+where
+    F: FieldName,
+{
+    fn default() -> Self {
+        Self::new()
+    }
 }
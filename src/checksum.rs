@@ -0,0 +1,444 @@
+use std::ops::Range;
+
+use crate::{Endian, Span};
+
+/// A checksum computed over a range of the encoded block.
+///
+/// [`Block::to_bytes`](crate::Block::to_bytes) computes the checksum after
+/// all fields are written, and [`Block::to_values`](crate::Block::to_values)
+/// verifies it after all fields are read. A checksum covering another one
+/// is computed after it, so two checksums cannot cover each other.
+pub struct Checksum {
+    pub algo: ChecksumAlgo,
+    pub range: ChecksumRange,
+    pub endian: Endian,
+}
+
+pub enum ChecksumAlgo {
+    /// CRC-32/ISO-HDLC, as used by Ethernet and zlib
+    Crc32,
+    /// CRC-16/CCITT-FALSE
+    Crc16Ccitt,
+    /// CRC-16/MODBUS
+    Crc16Modbus,
+    /// The 16-bit one's complement checksum of RFC 1071
+    Internet,
+}
+
+/// The part of the block covered by a checksum.
+pub enum ChecksumRange {
+    /// All fields before the checksum
+    Preceding,
+    /// The given number of fields right before the checksum, at most the number of fields before it
+    Last(usize),
+    /// The whole block, with the bytes of the checksum itself taken as zeros
+    All,
+}
+
+impl ChecksumAlgo {
+    /// Returns the encoded size of the checksum.
+    #[must_use]
+    pub fn size(&self) -> usize {
+        match self {
+            ChecksumAlgo::Crc32 => 4,
+            ChecksumAlgo::Crc16Ccitt | ChecksumAlgo::Crc16Modbus | ChecksumAlgo::Internet => 2,
+        }
+    }
+
+    #[must_use]
+    pub fn compute(&self, bytes: impl Iterator<Item = u8>) -> u64 {
+        match self {
+            ChecksumAlgo::Crc32 => {
+                let mut crc: u32 = 0xffff_ffff;
+                for x in bytes {
+                    crc ^= x as u32;
+                    for _ in 0..8 {
+                        let mask = (crc & 1).wrapping_neg();
+                        crc = (crc >> 1) ^ (0xedb8_8320 & mask);
+                    }
+                }
+                !crc as u64
+            }
+            ChecksumAlgo::Crc16Ccitt => {
+                let mut crc: u16 = 0xffff;
+                for x in bytes {
+                    crc ^= (x as u16) << 8;
+                    for _ in 0..8 {
+                        let mask = (crc >> 15).wrapping_neg();
+                        crc = (crc << 1) ^ (0x1021 & mask);
+                    }
+                }
+                crc as u64
+            }
+            ChecksumAlgo::Crc16Modbus => {
+                let mut crc: u16 = 0xffff;
+                for x in bytes {
+                    crc ^= x as u16;
+                    for _ in 0..8 {
+                        let mask = (crc & 1).wrapping_neg();
+                        crc = (crc >> 1) ^ (0xa001 & mask);
+                    }
+                }
+                crc as u64
+            }
+            ChecksumAlgo::Internet => {
+                let mut sum: u32 = 0;
+                for (i, x) in bytes.enumerate() {
+                    let x = x as u32;
+                    sum += if i % 2 == 0 { x << 8 } else { x };
+                    sum = (sum & 0xffff) + (sum >> 16);
+                }
+                !(sum as u16) as u64
+            }
+        }
+    }
+}
+
+impl Checksum {
+    /// Computes the checksum of the field at `index` over `buf`.
+    ///
+    /// `spans` are the spans of the fields up to and including the checksum.
+    pub(crate) fn compute(&self, buf: &[u8], index: usize, spans: &[Span]) -> u64 {
+        let this = spans[index];
+        let range = self.range(index, spans, buf.len());
+        let bytes = buf[range.clone()].iter().zip(range).map(|(x, i)| {
+            if this.range().contains(&i) {
+                0
+            } else {
+                *x
+            }
+        });
+        self.algo.compute(bytes)
+    }
+
+    fn range(&self, index: usize, spans: &[Span], end: usize) -> Range<usize> {
+        let this = spans[index];
+        match self.range {
+            ChecksumRange::Preceding => 0..this.pos,
+            ChecksumRange::Last(n) => spans[index - n].pos..this.pos,
+            ChecksumRange::All => 0..end,
+        }
+    }
+
+    /// Returns whether the checksum of the field at `index` covers the field at `other`.
+    pub(crate) fn covers(&self, index: usize, other: usize) -> bool {
+        match self.range {
+            ChecksumRange::Preceding => other < index,
+            ChecksumRange::Last(n) => index - n <= other && other < index,
+            ChecksumRange::All => other != index,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::{Block, Def, FieldName, Len, ToValuesError, Val, U64};
+
+    use super::*;
+
+    #[test]
+    fn test_algo() {
+        let check = b"123456789";
+        assert_eq!(
+            ChecksumAlgo::Crc32.compute(check.iter().copied()),
+            0xcbf43926
+        );
+        assert_eq!(
+            ChecksumAlgo::Crc16Ccitt.compute(check.iter().copied()),
+            0x29b1
+        );
+        assert_eq!(
+            ChecksumAlgo::Crc16Modbus.compute(check.iter().copied()),
+            0x4b37
+        );
+
+        // RFC 1071 example
+        let data = [0x00, 0x01, 0xf2, 0x03, 0xf4, 0xf5, 0xf6, 0xf7];
+        assert_eq!(
+            ChecksumAlgo::Internet.compute(data.iter().copied()),
+            !0xddf2 & 0xffff
+        );
+        let data = [0x00, 0x01, 0xf2];
+        assert_eq!(
+            ChecksumAlgo::Internet.compute(data.iter().copied()),
+            !0xf201 & 0xffff
+        );
+    }
+
+    #[test]
+    fn test_block() {
+        let block = get_block();
+
+        let mut values = HashMap::new();
+        values.insert(Name::VarInt, Val::VarInt(0x12));
+        let vec = b"12345678".to_vec();
+        values.insert(Name::BytesVarLen, Val::Bytes(&vec));
+
+        let mut buf = vec![0; 1024];
+        let end = block.to_bytes(&values, &mut buf).unwrap();
+        buf.truncate(end);
+
+        assert_eq!(end, 1 + 1 + 8 + 4 + 2 + 2);
+        // CRC-32 over the varint and the bytes with their length prefix
+        let crc32 = ChecksumAlgo::Crc32.compute(buf[..10].iter().copied());
+        assert_eq!(&buf[10..14], &(crc32 as u32).to_be_bytes());
+        // CRC-16 over the CRC-32
+        let crc16 = ChecksumAlgo::Crc16Ccitt.compute(buf[10..14].iter().copied());
+        assert_eq!(&buf[14..16], &(crc16 as u16).to_be_bytes());
+        // internet checksum over the whole block including itself sums to zero
+        let sum = ChecksumAlgo::Internet.compute(buf.iter().copied());
+        assert_eq!(sum, 0);
+
+        let mut values = HashMap::new();
+        block.to_values(&buf, &mut values).unwrap();
+        assert_eq!(values[&Name::Crc32].value.varint().unwrap(), crc32);
+        assert_eq!(values[&Name::Crc16].value.varint().unwrap(), crc16);
+        let mut values = HashMap::new();
+        block.compile().to_values(&buf, &mut values).unwrap();
+
+        let mut corrupted = buf.clone();
+        corrupted[3] ^= 1;
+        let mut values = HashMap::new();
+        let e = block.to_values(&corrupted, &mut values).unwrap_err();
+        assert_eq!(
+            e,
+            ToValuesError::ChecksumMismatch {
+                name: Name::Crc32,
+                expected: ChecksumAlgo::Crc32.compute(corrupted[..10].iter().copied()),
+                actual: crc32,
+            }
+        );
+        assert!(values.is_empty());
+        let e = block
+            .compile()
+            .to_values(&corrupted, &mut values)
+            .unwrap_err();
+        assert!(values.is_empty());
+        assert!(matches!(
+            e,
+            ToValuesError::ChecksumMismatch {
+                name: Name::Crc32,
+                ..
+            }
+        ));
+
+        let mut corrupted = buf.clone();
+        corrupted[17] ^= 1;
+        let e = block.to_values(&corrupted, &mut values).unwrap_err();
+        assert!(matches!(
+            e,
+            ToValuesError::ChecksumMismatch {
+                name: Name::Internet,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_covered_later() {
+        let mut block = Block::new();
+        block.add_field(Name::VarInt, Def::VarInt(U64::Var));
+        block.add_field(
+            Name::Internet,
+            Def::Checksum(Checksum {
+                algo: ChecksumAlgo::Internet,
+                range: ChecksumRange::All,
+                endian: Endian::Big,
+            }),
+        );
+        block.add_field(Name::BytesVarLen, Def::Bytes(Len::Var));
+        block.add_field(
+            Name::Crc16,
+            Def::Checksum(Checksum {
+                algo: ChecksumAlgo::Crc16Modbus,
+                range: ChecksumRange::Last(1),
+                endian: Endian::Little,
+            }),
+        );
+
+        let mut values = HashMap::new();
+        values.insert(Name::VarInt, Val::VarInt(7));
+        let vec = b"123456789".to_vec();
+        values.insert(Name::BytesVarLen, Val::Bytes(&vec));
+        let mut buf = vec![0; 1024];
+        let end = block.to_bytes(&values, &mut buf).unwrap();
+        buf.truncate(end);
+
+        // CRC-16/MODBUS over the bytes with their length prefix, least significant byte first
+        let crc16 = ChecksumAlgo::Crc16Modbus.compute(buf[3..13].iter().copied());
+        assert_eq!(&buf[13..], &(crc16 as u16).to_le_bytes());
+        // internet checksum over the whole block with the CRC-16 already in place
+        let mut zeroed = buf.clone();
+        zeroed[1..3].fill(0);
+        let sum = ChecksumAlgo::Internet.compute(zeroed.iter().copied());
+        assert_eq!(&buf[1..3], &(sum as u16).to_be_bytes());
+
+        let mut values = HashMap::new();
+        block.to_values(&buf, &mut values).unwrap();
+        assert_eq!(values[&Name::Crc16].value.varint().unwrap(), crc16);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_covering_each_other() {
+        let mut block = Block::<Name>::new();
+        block.add_field(
+            Name::Internet,
+            Def::Checksum(Checksum {
+                algo: ChecksumAlgo::Internet,
+                range: ChecksumRange::All,
+                endian: Endian::Big,
+            }),
+        );
+        block.add_field(
+            Name::Crc16,
+            Def::Checksum(Checksum {
+                algo: ChecksumAlgo::Crc16Ccitt,
+                range: ChecksumRange::Preceding,
+                endian: Endian::Big,
+            }),
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_last_too_many() {
+        let mut block = Block::<Name>::new();
+        block.add_field(Name::VarInt, Def::VarInt(U64::Var));
+        block.add_field(
+            Name::Crc16,
+            Def::Checksum(Checksum {
+                algo: ChecksumAlgo::Crc16Ccitt,
+                range: ChecksumRange::Last(2),
+                endian: Endian::Big,
+            }),
+        );
+    }
+
+    #[test]
+    fn test_trailer() {
+        let mut block = Block::new();
+        block.add_field(Name::BytesVarLen, Def::Bytes(Len::RemainingMinus(2)));
+        block.add_field(
+            Name::Crc16,
+            Def::Checksum(Checksum {
+                algo: ChecksumAlgo::Crc16Ccitt,
+                range: ChecksumRange::Preceding,
+                endian: Endian::Big,
+            }),
+        );
+
+        let mut values = HashMap::new();
+        let vec = b"123456789".to_vec();
+        values.insert(Name::BytesVarLen, Val::Bytes(&vec));
+
+        let mut buf = vec![0; 1024];
+        let end = block.to_bytes(&values, &mut buf).unwrap();
+        buf.truncate(end);
+        assert_eq!(&buf[9..], &[0x29, 0xb1]);
+
+        let mut values = HashMap::new();
+        let end = block.to_values(&buf, &mut values).unwrap();
+        assert_eq!(end, buf.len());
+        assert_eq!(values[&Name::BytesVarLen].value.bytes().unwrap(), &vec);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_trailer_too_long() {
+        let mut block = Block::<Name>::new();
+        block.add_field(Name::BytesVarLen, Def::Bytes(Len::RemainingMinus(2)));
+        block.add_field(
+            Name::Crc32,
+            Def::Checksum(Checksum {
+                algo: ChecksumAlgo::Crc32,
+                range: ChecksumRange::Preceding,
+                endian: Endian::Big,
+            }),
+        );
+    }
+
+    #[test]
+    fn test_trailer_too_short() {
+        let mut block = Block::new();
+        block.add_field(Name::BytesVarLen, Def::Bytes(Len::RemainingMinus(4)));
+        block.add_field(
+            Name::Crc16,
+            Def::Checksum(Checksum {
+                algo: ChecksumAlgo::Crc16Ccitt,
+                range: ChecksumRange::Preceding,
+                endian: Endian::Big,
+            }),
+        );
+        let invalid = ToValuesError::InvalidValue(Name::BytesVarLen);
+        let buf = [0; 8];
+        let e = block.to_values(&buf, &mut HashMap::new()).unwrap_err();
+        assert_eq!(e, invalid);
+        let e = block.compile().to_values(&buf, &mut HashMap::new());
+        assert_eq!(e.unwrap_err(), invalid);
+        let e = block.view(&buf).get(&Name::Crc16).unwrap_err();
+        assert_eq!(e, invalid);
+
+        let mut values = HashMap::new();
+        values.insert(Name::BytesVarLen, Val::Bytes(b"1234"));
+        let e = block.to_bytes(&values, &mut [0; 8]).unwrap_err();
+        assert_eq!(e, crate::ToBytesError::InvalidValue(Name::BytesVarLen));
+
+        // the trailer is complete once the fields after take the 4 bytes
+        block.add_field(Name::Internet, Def::FixedBytes(vec![0, 0]));
+        let end = block.to_bytes(&values, &mut [0; 8]).unwrap();
+        assert_eq!(end, 8);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_remaining_not_last() {
+        let mut block = Block::<Name>::new();
+        block.add_field(Name::BytesVarLen, Def::Bytes(Len::Remaining));
+        block.add_field(Name::VarInt, Def::VarInt(U64::Var));
+    }
+
+    fn get_block() -> Block<Name> {
+        let mut block = Block::new();
+        block.add_field(Name::VarInt, Def::VarInt(U64::Var));
+        block.add_field(Name::BytesVarLen, Def::Bytes(Len::Var));
+        block.add_field(
+            Name::Crc32,
+            Def::Checksum(Checksum {
+                algo: ChecksumAlgo::Crc32,
+                range: ChecksumRange::Preceding,
+                endian: Endian::Big,
+            }),
+        );
+        block.add_field(
+            Name::Crc16,
+            Def::Checksum(Checksum {
+                algo: ChecksumAlgo::Crc16Ccitt,
+                range: ChecksumRange::Last(1),
+                endian: Endian::Big,
+            }),
+        );
+        block.add_field(
+            Name::Internet,
+            Def::Checksum(Checksum {
+                algo: ChecksumAlgo::Internet,
+                range: ChecksumRange::All,
+                endian: Endian::Big,
+            }),
+        );
+        block
+    }
+
+    #[derive(Debug, PartialEq, Eq, Hash, Clone)]
+    enum Name {
+        VarInt,
+        BytesVarLen,
+        Crc32,
+        Crc16,
+        Internet,
+    }
+
+    impl FieldName for Name {}
+}
@@ -0,0 +1,239 @@
+use crate::Val;
+
+/// An encoding that the crate does not ship, used through [`Def::Custom`](crate::Def::Custom).
+///
+/// Custom fields take part in everything other fields do, such as defaults,
+/// constraints, limits on the block size, checksums, views and plans.
+pub trait Codec: Send + Sync {
+    /// Writes `value` at the start of `b` and returns the number of bytes written.
+    ///
+    /// Reporting more bytes than `b` holds fails the field with `InvalidValue`.
+    fn encode(&self, value: &Val, b: &mut [u8]) -> Result<usize, CodecError>;
+
+    /// Reads a value from the start of `b` and returns it with the number of bytes read.
+    fn decode<'buf>(&self, b: &'buf [u8]) -> Result<(Val<'buf>, usize), CodecError>;
+
+    /// Returns the number of bytes the value at the start of `b` takes.
+    ///
+    /// Used to skip over the field. Defaults to decoding the value.
+    fn size(&self, b: &[u8]) -> Result<usize, CodecError> {
+        self.decode(b).map(|(_, size)| size)
+    }
+
+    /// Returns the encoded size if it does not depend on the value.
+    ///
+    /// Views and plans trust this size to find the fields after this one
+    /// without decoding it, so it must match every length that
+    /// [`Codec::encode`], [`Codec::decode`] and [`Codec::size`] report. A
+    /// mismatch found while coding the field fails it with `InvalidValue`.
+    fn fixed_size(&self) -> Option<usize> {
+        None
+    }
+}
+
+/// An error from a [`Codec`], reported under the name of the field.
+#[derive(Debug, PartialEq, Eq)]
+pub enum CodecError {
+    InvalidValue,
+    /// Returned from [`Codec::encode`]
+    NotEnoughSpace,
+    /// Returned from [`Codec::decode`] and [`Codec::size`]
+    NotEnoughData,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::{Block, Constraint, Def, FieldName, ToBytesError, ToValuesError, Violation, U64};
+
+    use super::*;
+
+    /// Unsigned LEB128
+    struct Leb128;
+
+    impl Codec for Leb128 {
+        fn encode(&self, value: &Val, b: &mut [u8]) -> Result<usize, CodecError> {
+            let mut x = match value {
+                Val::VarInt(x) => *x,
+                _ => return Err(CodecError::InvalidValue),
+            };
+            let mut i = 0;
+            loop {
+                let byte = b.get_mut(i).ok_or(CodecError::NotEnoughSpace)?;
+                *byte = (x & 0x7f) as u8;
+                x >>= 7;
+                i += 1;
+                if x == 0 {
+                    return Ok(i);
+                }
+                *byte |= 0x80;
+            }
+        }
+
+        fn decode<'buf>(&self, b: &'buf [u8]) -> Result<(Val<'buf>, usize), CodecError> {
+            let mut x: u64 = 0;
+            for (i, byte) in b.iter().enumerate() {
+                if i == 10 {
+                    return Err(CodecError::InvalidValue);
+                }
+                x |= ((byte & 0x7f) as u64) << (7 * i);
+                if byte & 0x80 == 0 {
+                    return Ok((Val::VarInt(x), i + 1));
+                }
+            }
+            Err(CodecError::NotEnoughData)
+        }
+
+        fn size(&self, b: &[u8]) -> Result<usize, CodecError> {
+            match b.iter().position(|x| x & 0x80 == 0) {
+                Some(i) => Ok(i + 1),
+                None => Err(CodecError::NotEnoughData),
+            }
+        }
+    }
+
+    /// Always two bytes
+    struct Pair;
+
+    impl Codec for Pair {
+        fn encode(&self, _value: &Val, b: &mut [u8]) -> Result<usize, CodecError> {
+            if b.len() < 2 {
+                return Err(CodecError::NotEnoughSpace);
+            }
+            b[..2].copy_from_slice(b"ok");
+            Ok(2)
+        }
+
+        fn decode<'buf>(&self, b: &'buf [u8]) -> Result<(Val<'buf>, usize), CodecError> {
+            match b.get(..2) {
+                Some(x) => Ok((Val::Bytes(x), 2)),
+                None => Err(CodecError::NotEnoughData),
+            }
+        }
+
+        fn fixed_size(&self) -> Option<usize> {
+            Some(2)
+        }
+    }
+
+    #[test]
+    fn test_block() {
+        let block = get_block();
+
+        let mut values = HashMap::new();
+        values.insert(Name::Fixed, Val::Bytes(b"ok"));
+        values.insert(Name::Custom, Val::VarInt(300));
+        let mut buf = vec![0; 1024];
+        let end = block.to_bytes(&values, &mut buf).unwrap();
+        buf.truncate(end);
+        assert_eq!(buf, [b'o', b'k', 0xac, 0x02, 7]);
+
+        let mut decoded = HashMap::new();
+        block.to_values(&buf, &mut decoded).unwrap();
+        assert_eq!(decoded[&Name::Custom].value, Val::VarInt(300));
+        assert_eq!(decoded[&Name::Custom].pos, 2);
+        assert_eq!(decoded[&Name::Fixed].value, Val::Bytes(b"ok"));
+        let mut decoded = HashMap::new();
+        block.compile().to_values(&buf, &mut decoded).unwrap();
+        assert_eq!(decoded[&Name::Custom].value, Val::VarInt(300));
+        assert_eq!(block.compile().fixed_offset(&Name::Fixed), Some(0));
+        assert_eq!(block.compile().fixed_offset(&Name::Custom), None);
+
+        let mut view = block.view(&buf);
+        assert_eq!(view.get(&Name::VarInt).unwrap().unwrap().pos, 4);
+        let mut decoded = HashMap::new();
+        block
+            .to_selected_values(&buf, &[Name::VarInt], &mut decoded)
+            .unwrap();
+        assert_eq!(decoded[&Name::VarInt].value, Val::VarInt(7));
+    }
+
+    /// Claims more bytes than it was given, and a fixed size it does not keep
+    struct Liar;
+
+    impl Codec for Liar {
+        fn encode(&self, _value: &Val, b: &mut [u8]) -> Result<usize, CodecError> {
+            Ok(b.len() + 1)
+        }
+
+        fn decode<'buf>(&self, b: &'buf [u8]) -> Result<(Val<'buf>, usize), CodecError> {
+            Ok((Val::Bytes(b), b.len()))
+        }
+
+        fn fixed_size(&self) -> Option<usize> {
+            Some(1)
+        }
+    }
+
+    #[test]
+    fn test_contract() {
+        let mut block = Block::new();
+        block.add_field(Name::Custom, Def::Custom(Box::new(Liar)));
+
+        let mut values = HashMap::new();
+        values.insert(Name::Custom, Val::VarInt(1));
+        let e = block.to_bytes(&values, &mut [0; 4]).unwrap_err();
+        assert_eq!(e, ToBytesError::InvalidValue(Name::Custom));
+
+        let mut decoded = HashMap::new();
+        let e = block.to_values(&[1, 2], &mut decoded).unwrap_err();
+        assert_eq!(e, ToValuesError::InvalidValue(Name::Custom));
+        block.to_values(&[1], &mut decoded).unwrap();
+    }
+
+    #[test]
+    fn test_errors() {
+        let block = get_block();
+
+        let mut values = HashMap::new();
+        values.insert(Name::Fixed, Val::Bytes(b"ok"));
+        let e = block.to_bytes(&values, &mut [0; 16]).unwrap_err();
+        assert_eq!(e, ToBytesError::NoValueProvided(Name::Custom));
+        values.insert(Name::Custom, Val::Bytes(&[]));
+        let e = block.to_bytes(&values, &mut [0; 16]).unwrap_err();
+        assert_eq!(e, ToBytesError::InvalidValue(Name::Custom));
+        values.insert(Name::Custom, Val::VarInt(1 << 20));
+        let e = block.to_bytes(&values, &mut [0; 4]).unwrap_err();
+        assert_eq!(e, ToBytesError::NotEnoughSpace(Name::Custom));
+        values.insert(Name::Custom, Val::VarInt(1001));
+        let e = block.to_bytes(&values, &mut [0; 16]).unwrap_err();
+        assert!(matches!(
+            e,
+            ToBytesError::ConstraintViolated {
+                name: Name::Custom,
+                violation: Violation::Custom("small")
+            }
+        ));
+
+        let mut decoded = HashMap::new();
+        let e = block
+            .to_values(&[b'o', b'k', 0x80, 0x80], &mut decoded)
+            .unwrap_err();
+        assert_eq!(e, ToValuesError::NotEnoughData(Name::Custom));
+        let e = block.to_values(b"o", &mut decoded).unwrap_err();
+        assert_eq!(e, ToValuesError::NotEnoughData(Name::Fixed));
+    }
+
+    fn get_block() -> Block<Name> {
+        let mut block = Block::new();
+        block.add_field(Name::Fixed, Def::Custom(Box::new(Pair)));
+        block
+            .add_field(Name::Custom, Def::Custom(Box::new(Leb128)))
+            .add_constraint(Constraint::custom(
+                "small",
+                |x| !matches!(x, Val::VarInt(x) if *x > 1000 && *x != 1 << 20),
+            ));
+        block.add_field(Name::VarInt, Def::VarInt(U64::Fixed(7)));
+        block
+    }
+
+    #[derive(Debug, PartialEq, Eq, Hash, Clone)]
+    enum Name {
+        Fixed,
+        Custom,
+        VarInt,
+    }
+
+    impl FieldName for Name {}
+}
@@ -0,0 +1,281 @@
+use std::{fmt, ops::RangeInclusive};
+
+use crate::{Def, Val};
+
+/// A rule the value of a field must follow on both encode and decode.
+///
+/// Constraints are attached with [`Field::add_constraint`](crate::Field::add_constraint).
+pub enum Constraint {
+    /// The integer value must be in the range
+    Range(RangeInclusive<u64>),
+    /// The integer value must be one of the listed values
+    OneOf(Vec<u64>),
+    /// The length of the bytes or string value must be in the range
+    Len(RangeInclusive<usize>),
+    /// The value must pass the predicate; `name` identifies it in errors
+    ///
+    /// Integer fields pass the raw integer, before labels and scales apply.
+    Custom {
+        name: &'static str,
+        check: Box<dyn Fn(&Val) -> bool + Send + Sync>,
+    },
+}
+
+/// The reason a value broke a [`Constraint`].
+#[derive(Debug, PartialEq, Eq)]
+pub enum Violation {
+    OutOfRange {
+        min: u64,
+        max: u64,
+        actual: u64,
+    },
+    NotOneOf(u64),
+    LenOutOfRange {
+        min: usize,
+        max: usize,
+        actual: usize,
+    },
+    Custom(&'static str),
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Violation::OutOfRange { min, max, actual } => {
+                write!(f, "{actual} is not in {min}..={max}")
+            }
+            Violation::NotOneOf(x) => write!(f, "{x} is not one of the allowed values"),
+            Violation::LenOutOfRange { min, max, actual } => {
+                write!(f, "length {actual} is not in {min}..={max}")
+            }
+            Violation::Custom(name) => write!(f, "check `{name}` failed"),
+        }
+    }
+}
+
+impl Constraint {
+    /// Returns a constraint that checks the value with `check`.
+    #[must_use]
+    pub fn custom(
+        name: &'static str,
+        check: impl Fn(&Val) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        Constraint::Custom {
+            name,
+            check: Box::new(check),
+        }
+    }
+
+    /// Returns whether the constraint can be checked against values of `def`.
+    pub(crate) fn applies_to(&self, def: &Def) -> bool {
+        match self {
+            Constraint::Range(_) | Constraint::OneOf(_) => def.is_int(),
+            Constraint::Len(_) => matches!(def, Def::Bytes(_) | Def::FixedBytes(_) | Def::Str(_)),
+            Constraint::Custom { .. } => def.has_value(),
+        }
+    }
+
+    pub fn check(&self, value: &Val) -> Result<(), Violation> {
+        match (self, value) {
+            (Constraint::Range(range), Val::VarInt(x) | Val::Enum(x, _)) => match range.contains(x)
+            {
+                true => Ok(()),
+                false => Err(Violation::OutOfRange {
+                    min: *range.start(),
+                    max: *range.end(),
+                    actual: *x,
+                }),
+            },
+            (Constraint::OneOf(set), Val::VarInt(x) | Val::Enum(x, _)) => match set.contains(x) {
+                true => Ok(()),
+                false => Err(Violation::NotOneOf(*x)),
+            },
+            (Constraint::Len(range), Val::Bytes(x)) => check_len(range, x.len()),
+            (Constraint::Len(range), Val::Str(x)) => check_len(range, x.len()),
+            (Constraint::Custom { name, check }, _) => match check(value) {
+                true => Ok(()),
+                false => Err(Violation::Custom(name)),
+            },
+            // values of the wrong type are rejected by the field itself
+            (_, _) => Ok(()),
+        }
+    }
+}
+
+fn check_len(range: &RangeInclusive<usize>, len: usize) -> Result<(), Violation> {
+    match range.contains(&len) {
+        true => Ok(()),
+        false => Err(Violation::LenOutOfRange {
+            min: *range.start(),
+            max: *range.end(),
+            actual: len,
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::{Block, FieldName, Len, ToBytesError, ToValuesError, U64};
+
+    use super::*;
+
+    #[test]
+    fn test_check() {
+        let range = Constraint::Range(1..=3);
+        assert_eq!(range.check(&Val::VarInt(1)), Ok(()));
+        assert_eq!(
+            range.check(&Val::VarInt(4)),
+            Err(Violation::OutOfRange {
+                min: 1,
+                max: 3,
+                actual: 4
+            })
+        );
+
+        let one_of = Constraint::OneOf(vec![0x01, 0x05, 0x09]);
+        assert_eq!(one_of.check(&Val::VarInt(5)), Ok(()));
+        assert_eq!(one_of.check(&Val::VarInt(2)), Err(Violation::NotOneOf(2)));
+
+        let len = Constraint::Len(1..=2);
+        assert_eq!(len.check(&Val::Str("ab")), Ok(()));
+        assert_eq!(
+            len.check(&Val::Bytes(&[])),
+            Err(Violation::LenOutOfRange {
+                min: 1,
+                max: 2,
+                actual: 0
+            })
+        );
+
+        let even = Constraint::custom("even", |x| matches!(x, Val::VarInt(x) if x % 2 == 0));
+        assert_eq!(even.check(&Val::VarInt(2)), Ok(()));
+        assert_eq!(even.check(&Val::VarInt(3)), Err(Violation::Custom("even")));
+    }
+
+    #[test]
+    fn test_block() {
+        let block = get_block();
+
+        let mut values = HashMap::new();
+        values.insert(Name::Version, Val::VarInt(2));
+        values.insert(Name::Type, Val::VarInt(0x09));
+        values.insert(Name::Payload, Val::Bytes(&[1, 2]));
+
+        let mut buf = vec![0; 1024];
+        let end = block.to_bytes(&values, &mut buf).unwrap();
+        buf.truncate(end);
+        assert_eq!(buf, [2, 0x09, 2, 1, 2]);
+
+        let mut decoded = HashMap::new();
+        block.to_values(&buf, &mut decoded).unwrap();
+        assert_eq!(decoded[&Name::Version].value, Val::VarInt(2));
+
+        values.insert(Name::Version, Val::VarInt(4));
+        let e = block.to_bytes(&values, &mut [0; 1024]).unwrap_err();
+        assert_eq!(
+            e,
+            ToBytesError::ConstraintViolated {
+                name: Name::Version,
+                violation: Violation::OutOfRange {
+                    min: 1,
+                    max: 3,
+                    actual: 4
+                }
+            }
+        );
+        values.insert(Name::Version, Val::VarInt(2));
+
+        values.insert(Name::Payload, Val::Bytes(&[1, 2, 3]));
+        let e = block.to_bytes(&values, &mut [0; 1024]).unwrap_err();
+        assert_eq!(
+            e,
+            ToBytesError::ConstraintViolated {
+                name: Name::Payload,
+                violation: Violation::Custom("not 1, 2, 3")
+            }
+        );
+
+        let buf = [2, 0x02, 0];
+        let e = block.to_values(&buf, &mut decoded).unwrap_err();
+        assert_eq!(
+            e,
+            ToValuesError::ConstraintViolated {
+                name: Name::Type,
+                violation: Violation::NotOneOf(2)
+            }
+        );
+
+        let buf = [2, 0x01, 0];
+        let e = block.to_values(&buf, &mut decoded).unwrap_err();
+        let violation = Violation::LenOutOfRange {
+            min: 1,
+            max: 4,
+            actual: 0,
+        };
+        assert_eq!(
+            e,
+            ToValuesError::ConstraintViolated {
+                name: Name::Payload,
+                violation,
+            }
+        );
+        let e = block.view(&buf).get(&Name::Payload).unwrap_err();
+        assert!(matches!(e, ToValuesError::ConstraintViolated { .. }));
+    }
+
+    #[test]
+    fn test_plan() {
+        let mut block = Block::new();
+        block
+            .add_field(Name::Version, Def::Bytes(Len::Fixed(1)))
+            .add_constraint(Constraint::custom("nonzero", |x| x != &Val::Bytes(&[0])));
+
+        let mut values = HashMap::new();
+        let e = block.compile().to_values(&[0], &mut values).unwrap_err();
+        assert_eq!(
+            e,
+            ToValuesError::ConstraintViolated {
+                name: Name::Version,
+                violation: Violation::Custom("nonzero")
+            }
+        );
+        block.compile().to_values(&[1], &mut values).unwrap();
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_wrong_def() {
+        let mut block = Block::<Name>::new();
+        block
+            .add_field(Name::Version, Def::Bytes(Len::Var))
+            .add_constraint(Constraint::Range(1..=3));
+    }
+
+    fn get_block() -> Block<Name> {
+        let mut block = Block::new();
+        block
+            .add_field(Name::Version, Def::VarInt(U64::Var))
+            .add_constraint(Constraint::Range(1..=3));
+        block
+            .add_field(Name::Type, Def::VarInt(U64::Var))
+            .add_constraint(Constraint::OneOf(vec![0x01, 0x05, 0x09]));
+        block
+            .add_field(Name::Payload, Def::Bytes(Len::Var))
+            .add_constraint(Constraint::Len(1..=4))
+            .add_constraint(Constraint::custom("not 1, 2, 3", |x| {
+                x != &Val::Bytes(&[1, 2, 3])
+            }));
+        block
+    }
+
+    #[derive(Debug, PartialEq, Eq, Hash, Clone)]
+    enum Name {
+        Version,
+        Type,
+        Payload,
+    }
+
+    impl FieldName for Name {}
+}
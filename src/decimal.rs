@@ -0,0 +1,236 @@
+/// Binary-coded decimal digits taking `len` bytes, most significant first.
+///
+/// Decodes to [`Val::VarInt`](crate::Val::VarInt).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bcd {
+    /// Two digits per byte
+    Packed { len: usize },
+    /// One digit per byte in the low nibble
+    Unpacked { len: usize },
+}
+
+/// An integer written as `len` ASCII digits, filled with `pad` before the first digit.
+///
+/// Hex digits decode in either case and encode in upper case. Decodes to
+/// [`Val::VarInt`](crate::Val::VarInt).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AsciiNum {
+    pub len: usize,
+    /// 10 or 16
+    pub radix: u32,
+    /// Usually `b'0'` or `b' '`
+    pub pad: u8,
+}
+
+impl Bcd {
+    #[must_use]
+    pub fn len(&self) -> usize {
+        match self {
+            Bcd::Packed { len } | Bcd::Unpacked { len } => *len,
+        }
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the number in `x`, or `None` if a nibble is not a digit or the number overflows.
+    #[must_use]
+    pub fn decode(&self, x: &[u8]) -> Option<u64> {
+        let mut y: u64 = 0;
+        let mut push = |digit: u8| {
+            if digit > 9 {
+                return None;
+            }
+            y = y.checked_mul(10)?.checked_add(digit as u64)?;
+            Some(())
+        };
+        for byte in x {
+            match self {
+                Bcd::Packed { .. } => {
+                    push(byte >> 4)?;
+                    push(byte & 0x0f)?;
+                }
+                Bcd::Unpacked { .. } => {
+                    if byte >> 4 != 0 {
+                        return None;
+                    }
+                    push(*byte)?;
+                }
+            }
+        }
+        Some(y)
+    }
+
+    /// Writes `x` into `y` of [`Bcd::len`] bytes, or returns `false` if it has too many digits.
+    #[must_use]
+    pub fn encode(&self, mut x: u64, y: &mut [u8]) -> bool {
+        for byte in y.iter_mut().rev() {
+            *byte = match self {
+                Bcd::Packed { .. } => {
+                    let low = (x % 10) as u8;
+                    let high = (x / 10 % 10) as u8;
+                    x /= 100;
+                    high << 4 | low
+                }
+                Bcd::Unpacked { .. } => {
+                    let digit = (x % 10) as u8;
+                    x /= 10;
+                    digit
+                }
+            };
+        }
+        x == 0
+    }
+}
+
+impl AsciiNum {
+    pub(crate) fn is_valid(&self) -> bool {
+        matches!(self.radix, 10 | 16)
+    }
+
+    /// Returns the number in `x`, or `None` if it is not a padded number that fits in 64 bits.
+    #[must_use]
+    pub fn decode(&self, x: &[u8]) -> Option<u64> {
+        let start = x.iter().position(|x| *x != self.pad).unwrap_or(x.len());
+        let digits = &x[start..];
+        // a number padded with zeros may be all padding
+        let digits = match (digits.is_empty(), self.pad) {
+            (true, b'0') => b"0",
+            (true, _) => return None,
+            (false, _) => digits,
+        };
+        let digits = std::str::from_utf8(digits).ok()?;
+        if digits.starts_with('+') {
+            return None;
+        }
+        u64::from_str_radix(digits, self.radix).ok()
+    }
+
+    /// Writes `x` into `y` of `len` bytes, or returns `false` if it has too many digits.
+    #[must_use]
+    pub fn encode(&self, x: u64, y: &mut [u8]) -> bool {
+        let digits = match self.radix {
+            16 => format!("{x:X}"),
+            _ => x.to_string(),
+        };
+        if digits.len() > y.len() {
+            return false;
+        }
+        let (pad, rest) = y.split_at_mut(y.len() - digits.len());
+        pad.fill(self.pad);
+        rest.copy_from_slice(digits.as_bytes());
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::{Block, Def, FieldName, ToBytesError, ToValuesError, Val};
+
+    use super::*;
+
+    #[test]
+    fn test_bcd() {
+        let packed = Bcd::Packed { len: 3 };
+        let mut buf = [0; 3];
+        assert!(packed.encode(12345, &mut buf));
+        assert_eq!(buf, [0x01, 0x23, 0x45]);
+        assert_eq!(packed.decode(&buf), Some(12345));
+        assert!(!packed.encode(1_000_000, &mut buf));
+        assert_eq!(packed.decode(&[0x1a, 0, 0]), None);
+
+        let unpacked = Bcd::Unpacked { len: 3 };
+        assert!(unpacked.encode(42, &mut buf));
+        assert_eq!(buf, [0, 4, 2]);
+        assert_eq!(unpacked.decode(&buf), Some(42));
+        assert!(!unpacked.encode(1000, &mut buf));
+        assert_eq!(unpacked.decode(&[0x30, 0, 0]), None);
+
+        let packed = Bcd::Packed { len: 10 };
+        assert_eq!(packed.decode(&[0x99; 10]), None);
+    }
+
+    #[test]
+    fn test_ascii() {
+        let zeros = AsciiNum {
+            len: 4,
+            radix: 10,
+            pad: b'0',
+        };
+        let mut buf = [0; 4];
+        assert!(zeros.encode(42, &mut buf));
+        assert_eq!(&buf, b"0042");
+        assert_eq!(zeros.decode(&buf), Some(42));
+        assert!(zeros.encode(0, &mut buf));
+        assert_eq!(&buf, b"0000");
+        assert_eq!(zeros.decode(&buf), Some(0));
+        assert!(!zeros.encode(10000, &mut buf));
+        assert_eq!(zeros.decode(b"0-42"), None);
+        assert_eq!(zeros.decode(b"0+42"), None);
+
+        let spaces = AsciiNum {
+            len: 4,
+            radix: 16,
+            pad: b' ',
+        };
+        assert!(spaces.encode(0xbeef, &mut buf));
+        assert_eq!(&buf, b"BEEF");
+        assert!(spaces.encode(0xa, &mut buf));
+        assert_eq!(&buf, b"   A");
+        assert_eq!(spaces.decode(b"  ff"), Some(0xff));
+        assert_eq!(spaces.decode(b"    "), None);
+        assert_eq!(spaces.decode(b" f f"), None);
+    }
+
+    #[test]
+    fn test_block() {
+        let mut block = Block::new();
+        block.add_field(Name::Bcd, Def::Bcd(Bcd::Packed { len: 2 }));
+        block.add_field(
+            Name::Ascii,
+            Def::AsciiNum(AsciiNum {
+                len: 3,
+                radix: 10,
+                pad: b' ',
+            }),
+        );
+
+        let mut values = HashMap::new();
+        values.insert(Name::Bcd, Val::VarInt(1234));
+        values.insert(Name::Ascii, Val::VarInt(7));
+        let mut buf = vec![0; 1024];
+        let end = block.to_bytes(&values, &mut buf).unwrap();
+        buf.truncate(end);
+        assert_eq!(buf, [0x12, 0x34, b' ', b' ', b'7']);
+
+        let mut decoded = HashMap::new();
+        block.to_values(&buf, &mut decoded).unwrap();
+        assert_eq!(decoded[&Name::Bcd].value, Val::VarInt(1234));
+        assert_eq!(decoded[&Name::Ascii].value, Val::VarInt(7));
+
+        values.insert(Name::Ascii, Val::VarInt(1000));
+        let e = block.to_bytes(&values, &mut [0; 16]).unwrap_err();
+        assert_eq!(e, ToBytesError::OutOfRange(Name::Ascii));
+
+        let e = block
+            .to_values(&[0x12, 0x3f, b'1'], &mut decoded)
+            .unwrap_err();
+        assert_eq!(e, ToValuesError::InvalidValue(Name::Bcd));
+        let e = block
+            .to_values(&[0x12, 0x34, b'1'], &mut decoded)
+            .unwrap_err();
+        assert_eq!(e, ToValuesError::NotEnoughData(Name::Ascii));
+    }
+
+    #[derive(Debug, PartialEq, Eq, Hash, Clone)]
+    enum Name {
+        Bcd,
+        Ascii,
+    }
+
+    impl FieldName for Name {}
+}
@@ -0,0 +1,207 @@
+/// A sentinel sequence that ends a [`Len::Delimited`](crate::Len::Delimited) field.
+///
+/// Values are kept escaped in both directions: decoding returns the raw bytes
+/// before the delimiter, and encoding writes the value as is after checking it
+/// cannot be mistaken for the end of the field. [`Delimiter::escape`] and
+/// [`Delimiter::unescape`] convert between raw and plain bytes.
+pub struct Delimiter {
+    pub(crate) seq: Vec<u8>,
+    /// Whether the field extends past the delimiter
+    pub(crate) consume: bool,
+    /// Whether the value ends with the delimiter
+    pub(crate) include: bool,
+    /// A byte that makes the byte after it part of the value
+    pub(crate) escape: Option<u8>,
+    /// The maximum number of bytes before the delimiter
+    pub(crate) max_len: Option<usize>,
+}
+
+pub(crate) enum Scan {
+    /// The delimiter starts at the given offset
+    Found(usize),
+    NotFound,
+    TooLong,
+}
+
+impl Delimiter {
+    /// Returns a delimiter that is consumed but not included in the value, without escapes or length limit.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `seq` is empty.
+    #[must_use]
+    pub fn new(seq: Vec<u8>) -> Self {
+        assert!(!seq.is_empty());
+        Delimiter {
+            seq,
+            consume: true,
+            include: false,
+            escape: None,
+            max_len: None,
+        }
+    }
+
+    /// Makes the value end with the delimiter.
+    #[must_use]
+    pub fn included(self) -> Self {
+        Delimiter {
+            consume: true,
+            include: true,
+            ..self
+        }
+    }
+
+    /// Leaves the delimiter to the next field instead of consuming it.
+    #[must_use]
+    pub fn kept(self) -> Self {
+        Delimiter {
+            consume: false,
+            include: false,
+            ..self
+        }
+    }
+
+    /// Makes a byte after `escape` part of the value.
+    #[must_use]
+    pub fn with_escape(self, escape: u8) -> Self {
+        Delimiter {
+            escape: Some(escape),
+            ..self
+        }
+    }
+
+    /// Limits the number of bytes before the delimiter.
+    #[must_use]
+    pub fn with_max_len(self, max_len: usize) -> Self {
+        Delimiter {
+            max_len: Some(max_len),
+            ..self
+        }
+    }
+
+    #[must_use]
+    pub fn seq(&self) -> &[u8] {
+        &self.seq
+    }
+
+    /// Finds the first delimiter in `x` that is not escaped and at most `max_len` bytes in.
+    pub(crate) fn scan(&self, x: &[u8], max_len: Option<usize>) -> Scan {
+        self.scan_parts(x, &[], max_len)
+    }
+
+    /// Scans `x` followed by `y` as in [`Delimiter::scan`] without joining them.
+    fn scan_parts(&self, x: &[u8], y: &[u8], max_len: Option<usize>) -> Scan {
+        let len = x.len() + y.len();
+        let at = |i: usize| match i < x.len() {
+            true => x.get(i),
+            false => y.get(i - x.len()),
+        };
+        let mut i = 0;
+        while i < len {
+            if let Some(max_len) = max_len {
+                if i > max_len {
+                    return Scan::TooLong;
+                }
+            }
+            if at(i).copied() == self.escape {
+                i += 2;
+                continue;
+            }
+            let found = self
+                .seq
+                .iter()
+                .enumerate()
+                .all(|(j, byte)| at(i + j) == Some(byte));
+            if found {
+                return Scan::Found(i);
+            }
+            i += 1;
+        }
+        match max_len {
+            Some(max_len) if i > max_len => Scan::TooLong,
+            _ => Scan::NotFound,
+        }
+    }
+
+    /// Returns whether `x` followed by the delimiter reads back as `x`.
+    pub(crate) fn is_valid_content(&self, x: &[u8]) -> bool {
+        matches!(self.scan_parts(x, &self.seq, self.max_len), Scan::Found(i) if i == x.len())
+    }
+
+    /// Escapes the escape byte and the first byte of the delimiter in `x`.
+    #[must_use]
+    pub fn escape(&self, x: &[u8]) -> Vec<u8> {
+        let escape = match self.escape {
+            Some(escape) => escape,
+            None => return x.to_vec(),
+        };
+        let mut y = Vec::with_capacity(x.len());
+        for byte in x {
+            if *byte == escape || *byte == self.seq[0] {
+                y.push(escape);
+            }
+            y.push(*byte);
+        }
+        y
+    }
+
+    /// Removes the escape bytes from `x`.
+    #[must_use]
+    pub fn unescape(&self, x: &[u8]) -> Vec<u8> {
+        let escape = match self.escape {
+            Some(escape) => escape,
+            None => return x.to_vec(),
+        };
+        let mut y = Vec::with_capacity(x.len());
+        let mut escaped = false;
+        for byte in x {
+            if !escaped && *byte == escape {
+                escaped = true;
+                continue;
+            }
+            escaped = false;
+            y.push(*byte);
+        }
+        y
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scan() {
+        let delimiter = Delimiter::new(b"\r\n".to_vec());
+        assert!(matches!(delimiter.scan(b"ab\r\ncd", None), Scan::Found(2)));
+        assert!(matches!(delimiter.scan(b"\r\n", None), Scan::Found(0)));
+        assert!(matches!(delimiter.scan(b"ab\rcd\r", None), Scan::NotFound));
+
+        assert!(matches!(delimiter.scan(b"ab\r\n", Some(2)), Scan::Found(2)));
+        assert!(matches!(delimiter.scan(b"abc\r\n", Some(2)), Scan::TooLong));
+        assert!(matches!(delimiter.scan(b"ab", Some(2)), Scan::NotFound));
+        assert!(matches!(delimiter.scan(b"abc", Some(2)), Scan::TooLong));
+    }
+
+    #[test]
+    fn test_escape() {
+        let delimiter = Delimiter::new(vec![0xff, 0xff]).with_escape(b'\\');
+        let plain = [1, 0xff, 0xff, b'\\', 2];
+        let raw = delimiter.escape(&plain);
+        assert_eq!(raw, [1, b'\\', 0xff, b'\\', 0xff, b'\\', b'\\', 2]);
+        assert_eq!(delimiter.unescape(&raw), plain);
+        assert!(delimiter.is_valid_content(&raw));
+
+        let mut encoded = raw.clone();
+        encoded.extend([0xff, 0xff]);
+        assert!(matches!(delimiter.scan(&encoded, None), Scan::Found(8)));
+
+        assert!(!delimiter.is_valid_content(&plain));
+        assert!(!delimiter.is_valid_content(b"a\\"));
+        assert!(delimiter.is_valid_content(b"a\\\\"));
+
+        let delimiter = Delimiter::new(b"aba".to_vec());
+        assert!(!delimiter.is_valid_content(b"ab"));
+        assert!(delimiter.is_valid_content(b"bb"));
+    }
+}
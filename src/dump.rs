@@ -0,0 +1,182 @@
+use std::fmt::{self, Debug, Display};
+
+use octets::Octets;
+
+use crate::{Block, FieldName, Span, ToValuesError};
+
+/// Bytes shown per row
+const ROW_LEN: usize = 16;
+
+/// An annotated hex dump of an encoded buffer, decoded as it is displayed.
+///
+/// Each row shows the offset, the bytes in hex and ASCII, and on the first row
+/// of each field its name and decoded value. If decoding fails, the rest of the
+/// buffer is shown under the failing field with the error and its offset.
+pub struct HexDump<'block, 'buf, F>
+where
+    F: FieldName,
+{
+    block: &'block Block<F>,
+    buf: &'buf [u8],
+}
+
+impl<'block, 'buf, F> HexDump<'block, 'buf, F>
+where
+    F: FieldName,
+{
+    #[must_use]
+    pub fn new(block: &'block Block<F>, buf: &'buf [u8]) -> Self {
+        HexDump { block, buf }
+    }
+}
+
+impl<F> Display for HexDump<'_, '_, F>
+where
+    F: FieldName + Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut b = Octets::with_slice(self.buf);
+        let mut spans = Vec::new();
+        for field in self.block.fields() {
+            let pos = b.off();
+            let value = field
+                .to_value_(&mut b, self.block.limits())
+                .and_then(|value| self.block.check_size(field, b.off()).map(|()| value));
+            let value = match value {
+                Ok(value) => value,
+                Err(e) => {
+                    let rest = Span {
+                        pos,
+                        len: self.buf.len() - pos,
+                    };
+                    return write_rows(f, self.buf, rest, &format!("!! {e:?} at offset {pos:#x}"));
+                }
+            };
+            let note = match (field.def().has_value(), value.physical) {
+                (true, Some(physical)) => {
+                    format!("{:?} = {} ({physical})", field.name(), value.value)
+                }
+                (true, None) => format!("{:?} = {}", field.name(), value.value),
+                (false, _) => format!("{:?}", field.name()),
+            };
+            write_rows(f, self.buf, value.span, &note)?;
+            spans.push(value.span);
+        }
+        let end = b.off();
+        if self.block.has_checksums() {
+            if let Err(e) = self.block.check_checksums(&self.buf[..end], &spans) {
+                let pos = match &e {
+                    ToValuesError::ChecksumMismatch { name, .. } => {
+                        spans[self.block.position(name).unwrap()].pos
+                    }
+                    _ => end,
+                };
+                writeln!(f, "!! {e:?} at offset {pos:#x}")?;
+            }
+        }
+        let rest = Span {
+            pos: end,
+            len: self.buf.len() - end,
+        };
+        if rest.len != 0 {
+            write_rows(f, self.buf, rest, "(trailing)")?;
+        }
+        Ok(())
+    }
+}
+
+/// Writes the bytes of `span` in rows, with `note` on the first row.
+fn write_rows(f: &mut fmt::Formatter<'_>, buf: &[u8], span: Span, note: &str) -> fmt::Result {
+    let bytes = &buf[span.range()];
+    let mut rows: Vec<&[u8]> = bytes.chunks(ROW_LEN).collect();
+    if rows.is_empty() {
+        rows.push(&[]);
+    }
+    for (i, row) in rows.into_iter().enumerate() {
+        let hex = row
+            .iter()
+            .map(|x| format!("{x:02x}"))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let ascii = row
+            .iter()
+            .map(|x| match x.is_ascii_graphic() || *x == b' ' {
+                true => *x as char,
+                false => '.',
+            })
+            .collect::<String>();
+        let note = match i {
+            0 => note,
+            _ => "",
+        };
+        let line = format!(
+            "{:08x}  {hex:hex_len$}  {ascii:ROW_LEN$}  {note}",
+            span.pos + i * ROW_LEN,
+            hex_len = ROW_LEN * 3 - 1,
+        );
+        writeln!(f, "{}", line.trim_end())?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Def, Len, StrLen, U64};
+
+    use super::*;
+
+    #[test]
+    fn test_dump() {
+        let block = get_block();
+        let mut buf = vec![0xc0, 0, 0, 0, 0xde, 0xad, 0xbe, 0xef, 5];
+        buf.extend(b"hello");
+        buf.push(20);
+        buf.extend(0..20);
+        buf.extend([0, 0, 0xff]);
+        let dump = block.hex_dump(&buf).to_string();
+        let lines: Vec<_> = dump.lines().collect();
+        assert_eq!(
+            lines,
+            [
+                "00000000  c0 00 00 00 de ad be ef                          ........          Magic = 3735928559",
+                "00000008  05 68 65 6c 6c 6f                                .hello            Str = \"hello\"",
+                "0000000e  14 00 01 02 03 04 05 06 07 08 09 0a 0b 0c 0d 0e  ................  Bytes = 000102030405060708090a0b0c0d0e0f10111213",
+                "0000001e  0f 10 11 12 13                                   .....",
+                "00000023  00 00                                            ..                Padding",
+                "00000025  ff                                               .                 (trailing)",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_error() {
+        let block = get_block();
+        let buf = [0xc0, 0, 0, 0, 0xde, 0xad, 0xbe, 0xef, 5, b'h', 0xff];
+        let dump = block.hex_dump(&buf).to_string();
+        let lines: Vec<_> = dump.lines().collect();
+        assert_eq!(
+            lines[1],
+            "00000008  05 68 ff                                         .h.               !! NotEnoughData(Str) at offset 0x8"
+        );
+        assert_eq!(lines.len(), 2);
+    }
+
+    fn get_block() -> Block<Name> {
+        let mut block = Block::new();
+        block.add_field(Name::Magic, Def::VarInt(U64::Fixed(0xdeadbeef)));
+        block.add_field(Name::Str, Def::Str(StrLen::Var));
+        block.add_field(Name::Bytes, Def::Bytes(Len::Var));
+        block.add_field(Name::Padding, Def::Padding { len: 2, fill: 0 });
+        block
+    }
+
+    #[derive(Debug, PartialEq, Eq, Hash, Clone)]
+    enum Name {
+        Magic,
+        Str,
+        Bytes,
+        Padding,
+    }
+
+    impl FieldName for Name {}
+}
@@ -1,6 +1,15 @@
+use std::net::{Ipv4Addr, Ipv6Addr};
+
 use octets::{Octets, OctetsMut};
 
-use crate::{FieldName, ToBytesError, ToValuesError, Val, ValInfo};
+use crate::{
+    delimiter::Scan, limits::min_limit, AsciiNum, Bcd, Checksum, Codec, CodecError, Constraint,
+    Delimiter, Endian, FieldName, FixedPoint, Labels, Limits, Scale, Span, Timestamp, Tlv, TlvInt,
+    ToBytesError, ToValuesError, Val, ValInfo, Violation,
+};
+
+/// The largest value a QUIC variable-length integer holds
+const MAX_VARINT: u64 = (1 << 62) - 1;
 
 pub struct Field<F>
 where
@@ -8,15 +17,37 @@ where
 {
     name: F,
     def: Def,
+    max_len: Option<usize>,
+    constraints: Vec<Constraint>,
+    default: Option<DefaultVal>,
+    labels: Option<Labels>,
+    scale: Option<Scale>,
+}
+
+enum DefaultVal {
+    Static(Val<'static>),
+    Fn(Box<dyn Fn() -> Val<'static> + Send + Sync>),
 }
 
 impl<F> Field<F>
 where
     F: FieldName,
 {
+    /// # Panics
+    ///
+    /// Panics if `def` is malformed, such as alignment to zero bytes.
     #[must_use]
     pub fn new(name: F, def: Def) -> Self {
-        Field { name, def }
+        def.check_rep();
+        Field {
+            name,
+            def,
+            max_len: None,
+            constraints: Vec::new(),
+            default: None,
+            labels: None,
+            scale: None,
+        }
     }
 
     #[must_use]
@@ -29,10 +60,171 @@ where
         &self.def
     }
 
+    #[must_use]
+    pub fn max_len(&self) -> Option<usize> {
+        self.max_len
+    }
+
+    /// Limits the length of the value of a variable-length bytes or string field on decode.
+    ///
+    /// The tighter of this and [`Limits::max_len`] applies.
+    pub fn set_max_len(&mut self, max_len: usize) -> &mut Self {
+        self.max_len = Some(max_len);
+        self
+    }
+
+    #[must_use]
+    pub fn constraints(&self) -> &[Constraint] {
+        &self.constraints
+    }
+
+    /// Adds a constraint checked against the value on both encode and decode.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the constraint cannot apply to values of the field.
+    pub fn add_constraint(&mut self, constraint: Constraint) -> &mut Self {
+        assert!(constraint.applies_to(&self.def));
+        self.constraints.push(constraint);
+        self
+    }
+
+    #[must_use]
+    pub fn labels(&self) -> Option<&Labels> {
+        self.labels.as_ref()
+    }
+
+    /// Names the values of an integer field.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the field is not an integer.
+    pub fn set_labels(&mut self, labels: Labels) -> &mut Self {
+        assert!(self.def.is_int());
+        self.labels = Some(labels);
+        self
+    }
+
+    #[must_use]
+    pub fn scale(&self) -> Option<&Scale> {
+        self.scale.as_ref()
+    }
+
+    /// Exposes the physical value of an integer field in [`ValInfo::physical`]
+    /// and accepts physical values as [`Val::F32`] or [`Val::F64`] on encode.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the field is not an integer.
+    pub fn set_scale(&mut self, scale: Scale) -> &mut Self {
+        assert!(self.def.is_int());
+        self.scale = Some(scale);
+        self
+    }
+
+    /// Returns whether decoding does more than read the value.
+    pub(crate) fn checks_value(&self) -> bool {
+        !self.constraints.is_empty() || self.labels.is_some() || self.scale.is_some()
+    }
+
+    /// Maps labels, labeled values and physical values to plain integers.
+    ///
+    /// Returns `None` if the value is already raw.
+    fn to_raw<'a>(&self, value: &'a Val<'a>) -> Result<Option<Val<'a>>, ToBytesError<F>> {
+        let x = match value {
+            Val::Enum(x, label) => match self.labels() {
+                Some(labels) if labels.label(*x) == Some(label.as_ref()) => *x,
+                _ => return Err(ToBytesError::InvalidValue(self.name().clone())),
+            },
+            Val::Str(x) if self.labels.is_some() => match self.labels().unwrap().value(x) {
+                Some(x) => x,
+                None => return Err(ToBytesError::InvalidValue(self.name().clone())),
+            },
+            Val::F32(_) | Val::F64(_) if self.scale.is_some() => {
+                match self.scale().unwrap().to_raw(value.f64().unwrap()) {
+                    Some(x) => x,
+                    None => return Err(ToBytesError::OutOfRange(self.name().clone())),
+                }
+            }
+            Val::VarInt(x) if self.labels.is_some() => *x,
+            _ => return Ok(None),
+        };
+        if let Some(labels) = self.labels() {
+            if !labels.allows_unknown() && labels.label(x).is_none() {
+                return Err(ToBytesError::InvalidValue(self.name().clone()));
+            }
+        }
+        Ok(Some(Val::VarInt(x)))
+    }
+
+    fn check_constraints(&self, value: &Val) -> Result<(), Violation> {
+        for constraint in &self.constraints {
+            constraint.check(value)?;
+        }
+        Ok(())
+    }
+
+    /// Sets the value encoded when none is provided.
+    pub fn set_default(&mut self, value: Val<'static>) -> &mut Self {
+        self.default = Some(DefaultVal::Static(value));
+        self
+    }
+
+    /// Sets a function computing the value encoded when none is provided.
+    pub fn set_default_with(
+        &mut self,
+        f: impl Fn() -> Val<'static> + Send + Sync + 'static,
+    ) -> &mut Self {
+        self.default = Some(DefaultVal::Fn(Box::new(f)));
+        self
+    }
+
+    /// Returns whether the field has a default that a provided value overrides.
+    ///
+    /// Constant fields are told apart by [`Def::is_constant`].
+    #[must_use]
+    pub fn has_default(&self) -> bool {
+        self.default.is_some()
+    }
+
+    #[allow(clippy::redundant_pattern_matching)]
     pub fn to_bytes(&self, value: Option<&Val>, b: &mut OctetsMut) -> Result<(), ToBytesError<F>> {
+        let default;
+        let value = match (value, &self.default) {
+            (Some(value), _) | (None, Some(DefaultVal::Static(value))) => Some(value),
+            (None, Some(DefaultVal::Fn(f))) => {
+                default = f();
+                Some(&default)
+            }
+            (None, None) => None,
+        };
+        let raw;
+        let value = match value {
+            Some(value) => match self.to_raw(value)? {
+                Some(x) => {
+                    raw = x;
+                    Some(&raw)
+                }
+                None => Some(value),
+            },
+            None => None,
+        };
+        if let Some(value) = value {
+            if let Err(violation) = self.check_constraints(value) {
+                return Err(ToBytesError::ConstraintViolated {
+                    name: self.name().clone(),
+                    violation,
+                });
+            }
+        }
         match self.def() {
             Def::VarInt(x) => {
                 let y = value;
+                if let Some(Val::VarInt(y)) = y {
+                    if *y > MAX_VARINT {
+                        return Err(ToBytesError::OutOfRange(self.name().clone()));
+                    }
+                }
                 match (x, y) {
                     (U64::Fixed(x), Some(Val::VarInt(y))) => {
                         if *y != *x {
@@ -60,40 +252,60 @@ where
                     }
                 };
             }
-            Def::Bytes(len) => {
-                match len {
-                    Len::Fixed(len) => match value {
-                        Some(Val::Bytes(x)) => {
-                            if x.len() != *len {
-                                return Err(ToBytesError::InvalidValue(self.name().clone()));
-                            }
-                            if let Err(_) = b.put_bytes(x) {
-                                return Err(ToBytesError::NotEnoughSpace(self.name().clone()));
-                            };
-                        }
-                        _ => {
-                            return Err(ToBytesError::NoValueProvided(self.name().clone()));
+            Def::Bytes(len) => match len {
+                Len::Fixed(len) => match value {
+                    Some(Val::Bytes(x)) => {
+                        if x.len() != *len {
+                            return Err(ToBytesError::InvalidValue(self.name().clone()));
                         }
-                    },
-                    Len::Var => {
-                        match value {
-                            Some(Val::Bytes(x)) => {
-                                // length prefix
-                                if let Err(_) = b.put_varint(x.len() as u64) {
-                                    return Err(ToBytesError::NotEnoughSpace(self.name().clone()));
-                                };
-                                // data
-                                if let Err(_) = b.put_bytes(x) {
-                                    return Err(ToBytesError::NotEnoughSpace(self.name().clone()));
-                                };
-                            }
-                            _ => {
-                                return Err(ToBytesError::NoValueProvided(self.name().clone()));
+                        if b.put_bytes(x).is_err() {
+                            return Err(ToBytesError::NotEnoughSpace(self.name().clone()));
+                        };
+                    }
+                    _ => {
+                        return Err(ToBytesError::NoValueProvided(self.name().clone()));
+                    }
+                },
+                Len::Var => match value {
+                    Some(Val::Bytes(x)) => self.put_var_bytes(x, b)?,
+                    _ => {
+                        return Err(ToBytesError::NoValueProvided(self.name().clone()));
+                    }
+                },
+                Len::Remaining | Len::RemainingMinus(_) => match value {
+                    Some(Val::Bytes(x)) => {
+                        if b.put_bytes(x).is_err() {
+                            return Err(ToBytesError::NotEnoughSpace(self.name().clone()));
+                        };
+                    }
+                    _ => {
+                        return Err(ToBytesError::NoValueProvided(self.name().clone()));
+                    }
+                },
+                Len::Delimited(d) => match value {
+                    Some(Val::Bytes(x)) => {
+                        let x = match (d.include, x.strip_suffix(&d.seq[..])) {
+                            (true, Some(x)) => x,
+                            (true, None) => {
+                                return Err(ToBytesError::InvalidValue(self.name().clone()))
                             }
+                            (false, _) => x,
+                        };
+                        if !d.is_valid_content(x) {
+                            return Err(ToBytesError::InvalidValue(self.name().clone()));
                         }
+                        if b.put_bytes(x).is_err() {
+                            return Err(ToBytesError::NotEnoughSpace(self.name().clone()));
+                        };
+                        if d.consume && b.put_bytes(&d.seq).is_err() {
+                            return Err(ToBytesError::NotEnoughSpace(self.name().clone()));
+                        };
                     }
-                }
-            }
+                    _ => {
+                        return Err(ToBytesError::NoValueProvided(self.name().clone()));
+                    }
+                },
+            },
             Def::FixedBytes(x) => {
                 if let Some(y) = value {
                     match y {
@@ -111,36 +323,227 @@ where
                     return Err(ToBytesError::NotEnoughSpace(self.name().clone()));
                 };
             }
+            Def::Str(len) => {
+                let x = match value {
+                    Some(Val::Str(x)) => x.as_bytes(),
+                    Some(_) => return Err(ToBytesError::InvalidValue(self.name().clone())),
+                    None => return Err(ToBytesError::NoValueProvided(self.name().clone())),
+                };
+                match len {
+                    StrLen::Nul => {
+                        if x.contains(&0) {
+                            return Err(ToBytesError::InvalidValue(self.name().clone()));
+                        }
+                        if b.put_bytes(x).is_err() || b.put_u8(0).is_err() {
+                            return Err(ToBytesError::NotEnoughSpace(self.name().clone()));
+                        };
+                    }
+                    StrLen::Padded { len, pad } => {
+                        // trailing pad bytes would not survive decoding
+                        if x.len() > *len || x.last() == Some(pad) {
+                            return Err(ToBytesError::InvalidValue(self.name().clone()));
+                        }
+                        if b.cap() < *len {
+                            return Err(ToBytesError::NotEnoughSpace(self.name().clone()));
+                        }
+                        b.put_bytes(x).unwrap();
+                        for _ in x.len()..*len {
+                            b.put_u8(*pad).unwrap();
+                        }
+                    }
+                    StrLen::Var => self.put_var_bytes(x, b)?,
+                }
+            }
+            Def::Tlv(x) => match value {
+                Some(Val::Tlv(items)) => {
+                    if let Err(e) = x.to_bytes(items, b) {
+                        return Err(e.with_name(self.name().clone()));
+                    }
+                }
+                Some(_) => return Err(ToBytesError::InvalidValue(self.name().clone())),
+                None => return Err(ToBytesError::NoValueProvided(self.name().clone())),
+            },
+            Def::Align { to, fill } => {
+                let len = (to - b.off() % to) % to;
+                self.put_fill(len, *fill, b)?;
+            }
+            Def::Padding { len, fill } => self.put_fill(*len, *fill, b)?,
+            Def::Reserved { len, .. } => self.put_fill(*len, 0, b)?,
+            Def::F32(endian) => match value {
+                Some(Val::F32(x)) => self.put_uint(x.to_bits() as u64, 4, *endian, b)?,
+                Some(_) => return Err(ToBytesError::InvalidValue(self.name().clone())),
+                None => return Err(ToBytesError::NoValueProvided(self.name().clone())),
+            },
+            Def::F64(endian) => match value {
+                Some(Val::F32(x)) => self.put_uint((*x as f64).to_bits(), 8, *endian, b)?,
+                Some(Val::F64(x)) => self.put_uint(x.to_bits(), 8, *endian, b)?,
+                Some(_) => return Err(ToBytesError::InvalidValue(self.name().clone())),
+                None => return Err(ToBytesError::NoValueProvided(self.name().clone())),
+            },
+            Def::FixedPoint(x) => {
+                let y = match value {
+                    Some(Val::F32(y)) => *y as f64,
+                    Some(Val::F64(y)) => *y,
+                    Some(_) => return Err(ToBytesError::InvalidValue(self.name().clone())),
+                    None => return Err(ToBytesError::NoValueProvided(self.name().clone())),
+                };
+                let y = match x.encode(y) {
+                    Some(y) => y,
+                    None => return Err(ToBytesError::InvalidValue(self.name().clone())),
+                };
+                self.put_uint(y, x.size(), x.endian, b)?;
+            }
+            Def::Bool => match value {
+                Some(Val::Bool(x)) => self.put_uint(*x as u64, 1, Endian::Big, b)?,
+                Some(_) => return Err(ToBytesError::InvalidValue(self.name().clone())),
+                None => return Err(ToBytesError::NoValueProvided(self.name().clone())),
+            },
+            Def::Ipv4 | Def::Ipv6 | Def::Mac | Def::Uuid => {
+                let ipv4;
+                let ipv6;
+                let x: &[u8] = match (self.def(), value) {
+                    (Def::Ipv4, Some(Val::Ipv4(x))) => {
+                        ipv4 = x.octets();
+                        &ipv4
+                    }
+                    (Def::Ipv6, Some(Val::Ipv6(x))) => {
+                        ipv6 = x.octets();
+                        &ipv6
+                    }
+                    (Def::Mac, Some(Val::Mac(x))) => x,
+                    (Def::Uuid, Some(Val::Uuid(x))) => x,
+                    (_, Some(_)) => {
+                        return Err(ToBytesError::InvalidValue(self.name().clone()));
+                    }
+                    (_, None) => {
+                        return Err(ToBytesError::NoValueProvided(self.name().clone()));
+                    }
+                };
+                if b.put_bytes(x).is_err() {
+                    return Err(ToBytesError::NotEnoughSpace(self.name().clone()));
+                };
+            }
+            Def::Bcd(_) | Def::AsciiNum(_) => {
+                let x = match value {
+                    Some(Val::VarInt(x)) => *x,
+                    Some(_) => return Err(ToBytesError::InvalidValue(self.name().clone())),
+                    None => return Err(ToBytesError::NoValueProvided(self.name().clone())),
+                };
+                let mut y = vec![0; self.def().fixed_size().unwrap()];
+                let fits = match self.def() {
+                    Def::Bcd(bcd) => bcd.encode(x, &mut y),
+                    Def::AsciiNum(num) => num.encode(x, &mut y),
+                    _ => unreachable!(),
+                };
+                if !fits {
+                    return Err(ToBytesError::OutOfRange(self.name().clone()));
+                }
+                if b.put_bytes(&y).is_err() {
+                    return Err(ToBytesError::NotEnoughSpace(self.name().clone()));
+                };
+            }
+            Def::Timestamp(x) => {
+                let y = match value {
+                    Some(Val::Time(y)) => *y,
+                    Some(_) => return Err(ToBytesError::InvalidValue(self.name().clone())),
+                    None => return Err(ToBytesError::NoValueProvided(self.name().clone())),
+                };
+                let y = match x.encode(y) {
+                    Some(y) => y,
+                    None => return Err(ToBytesError::InvalidValue(self.name().clone())),
+                };
+                self.put_uint(y, x.size(), x.endian(), b)?;
+            }
+            Def::Checksum(x) => {
+                // placeholder until the block computes the checksum
+                let zeros = [0; 8];
+                if b.put_bytes(&zeros[..x.algo.size()]).is_err() {
+                    return Err(ToBytesError::NotEnoughSpace(self.name().clone()));
+                };
+            }
+            Def::Custom(x) => {
+                let y = match value {
+                    Some(y) => y,
+                    None => return Err(ToBytesError::NoValueProvided(self.name().clone())),
+                };
+                let len = match x.encode(y, b.as_mut()) {
+                    Ok(len) => len,
+                    Err(CodecError::NotEnoughSpace) => {
+                        return Err(ToBytesError::NotEnoughSpace(self.name().clone()))
+                    }
+                    Err(_) => return Err(ToBytesError::InvalidValue(self.name().clone())),
+                };
+                if !is_codec_len(x.as_ref(), len, b.cap()) {
+                    return Err(ToBytesError::InvalidValue(self.name().clone()));
+                }
+                b.skip(len).unwrap();
+            }
+        }
+        Ok(())
+    }
+
+    fn put_fill(&self, len: usize, fill: u8, b: &mut OctetsMut) -> Result<(), ToBytesError<F>> {
+        if b.cap() < len {
+            return Err(ToBytesError::NotEnoughSpace(self.name().clone()));
         }
+        for _ in 0..len {
+            b.put_u8(fill).unwrap();
+        }
+        Ok(())
+    }
+
+    fn put_uint(
+        &self,
+        x: u64,
+        size: usize,
+        endian: Endian,
+        b: &mut OctetsMut,
+    ) -> Result<(), ToBytesError<F>> {
+        let mut y = [0; 8];
+        endian.write(x, &mut y[..size]);
+        if b.put_bytes(&y[..size]).is_err() {
+            return Err(ToBytesError::NotEnoughSpace(self.name().clone()));
+        };
+        Ok(())
+    }
+
+    fn put_var_bytes(&self, x: &[u8], b: &mut OctetsMut) -> Result<(), ToBytesError<F>> {
+        // length prefix
+        if b.put_varint(x.len() as u64).is_err() {
+            return Err(ToBytesError::NotEnoughSpace(self.name().clone()));
+        };
+        // data
+        if b.put_bytes(x).is_err() {
+            return Err(ToBytesError::NotEnoughSpace(self.name().clone()));
+        };
         Ok(())
     }
 
     pub fn to_value<'buf>(&self, b: &mut Octets<'buf>) -> Result<ValInfo<'buf>, ToValuesError<F>> {
+        self.to_value_(b, &Limits::default())
+    }
+
+    pub(crate) fn to_value_<'buf>(
+        &self,
+        b: &mut Octets<'buf>,
+        limits: &Limits,
+    ) -> Result<ValInfo<'buf>, ToValuesError<F>> {
+        let max_len = min_limit(self.max_len, limits.max_len);
         let pos = b.off();
+        let mut prefix = None;
 
-        match self.def() {
+        let value = match self.def() {
             Def::VarInt(x) => {
                 let y = match b.get_varint() {
                     Ok(y) => y,
                     Err(_) => return Err(ToValuesError::NotEnoughData(self.name().clone())),
                 };
-                match x {
-                    U64::Fixed(x) => {
-                        if *x != y {
-                            return Err(ToValuesError::InvalidValue(self.name().clone()));
-                        }
-                        return Ok(ValInfo {
-                            value: Val::VarInt(y),
-                            pos,
-                        });
+                if let U64::Fixed(x) = x {
+                    if *x != y {
+                        return Err(ToValuesError::InvalidValue(self.name().clone()));
                     }
-                    U64::Var => {
-                        return Ok(ValInfo {
-                            value: Val::VarInt(y),
-                            pos,
-                        });
-                    }
-                };
+                }
+                Val::VarInt(y)
             }
             Def::Bytes(len) => match len {
                 Len::Fixed(len) => {
@@ -148,16 +551,27 @@ where
                         Ok(x) => x,
                         Err(_) => return Err(ToValuesError::NotEnoughData(self.name().clone())),
                     };
-                    let value = Val::Bytes(x.buf());
-                    return Ok(ValInfo { value, pos });
+                    Val::Bytes(x.buf())
                 }
                 Len::Var => {
-                    let x = match b.get_bytes_with_varint_length() {
-                        Ok(x) => x,
-                        Err(_) => return Err(ToValuesError::NotEnoughData(self.name().clone())),
+                    Val::Bytes(self.get_var_bytes(b, TlvInt::Varint, &mut prefix, max_len)?)
+                }
+                Len::Remaining | Len::RemainingMinus(_) => {
+                    let len = self.remaining_len(len, b, max_len)?;
+                    Val::Bytes(b.get_bytes(len).unwrap().buf())
+                }
+                Len::Delimited(d) => {
+                    let len = self.delimited_len(d, b, max_len)?;
+                    let rest = &b.buf()[b.off()..];
+                    let x = match d.include {
+                        true => &rest[..len + d.seq.len()],
+                        false => &rest[..len],
                     };
-                    let value = Val::Bytes(x.buf());
-                    return Ok(ValInfo { value, pos });
+                    b.skip(len).unwrap();
+                    if d.consume {
+                        b.skip(d.seq.len()).unwrap();
+                    }
+                    Val::Bytes(x)
                 }
             },
             Def::FixedBytes(x) => {
@@ -168,19 +582,477 @@ where
                 if y.buf() != x {
                     return Err(ToValuesError::InvalidValue(self.name().clone()));
                 }
-                return Ok(ValInfo {
-                    value: Val::Bytes(y.buf()),
-                    pos,
-                });
+                Val::Bytes(y.buf())
+            }
+            Def::Str(len) => {
+                let x = match len {
+                    StrLen::Nul => {
+                        let len = self.nul_pos(b, max_len)?;
+                        let x = b.get_bytes(len).unwrap();
+                        b.skip(1).unwrap();
+                        x.buf()
+                    }
+                    StrLen::Padded { len, pad } => {
+                        let x = match b.get_bytes(*len) {
+                            Ok(x) => x.buf(),
+                            Err(_) => {
+                                return Err(ToValuesError::NotEnoughData(self.name().clone()))
+                            }
+                        };
+                        let end = x.iter().rposition(|y| y != pad).map_or(0, |i| i + 1);
+                        &x[..end]
+                    }
+                    StrLen::Var => self.get_var_bytes(b, TlvInt::Varint, &mut prefix, max_len)?,
+                };
+                match std::str::from_utf8(x) {
+                    Ok(x) => Val::Str(x),
+                    Err(_) => return Err(ToValuesError::InvalidUtf8(self.name().clone())),
+                }
+            }
+            Def::Tlv(x) => {
+                let y = self.get_tlv_bytes(x, b, &mut prefix, max_len)?;
+                match x.to_items(y, limits) {
+                    Ok(items) => Val::Tlv(items),
+                    Err(e) => return Err(e.with_name(self.name().clone())),
+                }
+            }
+            Def::Align { to, .. } => {
+                let len = (to - b.off() % to) % to;
+                match b.get_bytes(len) {
+                    Ok(x) => Val::Bytes(x.buf()),
+                    Err(_) => return Err(ToValuesError::NotEnoughData(self.name().clone())),
+                }
+            }
+            Def::Padding { len, .. } => match b.get_bytes(*len) {
+                Ok(x) => Val::Bytes(x.buf()),
+                Err(_) => return Err(ToValuesError::NotEnoughData(self.name().clone())),
+            },
+            Def::Reserved { len, check } => {
+                let x = match b.get_bytes(*len) {
+                    Ok(x) => x.buf(),
+                    Err(_) => return Err(ToValuesError::NotEnoughData(self.name().clone())),
+                };
+                if *check && x.iter().any(|x| *x != 0) {
+                    return Err(ToValuesError::InvalidValue(self.name().clone()));
+                }
+                Val::Bytes(x)
+            }
+            Def::F32(endian) => {
+                let x = self.get_uint(b, 4, *endian)?;
+                Val::F32(f32::from_bits(x as u32))
+            }
+            Def::F64(endian) => Val::F64(f64::from_bits(self.get_uint(b, 8, *endian)?)),
+            Def::FixedPoint(x) => Val::F64(x.decode(self.get_uint(b, x.size(), x.endian)?)),
+            Def::Bool => match self.get_uint(b, 1, Endian::Big)? {
+                0 => Val::Bool(false),
+                1 => Val::Bool(true),
+                _ => return Err(ToValuesError::InvalidValue(self.name().clone())),
+            },
+            Def::Ipv4 => Val::Ipv4(Ipv4Addr::from(self.get_array::<4>(b)?)),
+            Def::Ipv6 => Val::Ipv6(Ipv6Addr::from(self.get_array::<16>(b)?)),
+            Def::Mac => Val::Mac(self.get_array(b)?),
+            Def::Uuid => Val::Uuid(self.get_array(b)?),
+            Def::Bcd(_) | Def::AsciiNum(_) => {
+                let x = match b.get_bytes(self.def().fixed_size().unwrap()) {
+                    Ok(x) => x.buf(),
+                    Err(_) => return Err(ToValuesError::NotEnoughData(self.name().clone())),
+                };
+                let y = match self.def() {
+                    Def::Bcd(bcd) => bcd.decode(x),
+                    Def::AsciiNum(num) => num.decode(x),
+                    _ => unreachable!(),
+                };
+                match y {
+                    Some(y) => Val::VarInt(y),
+                    None => return Err(ToValuesError::InvalidValue(self.name().clone())),
+                }
+            }
+            Def::Timestamp(x) => match x.decode(self.get_uint(b, x.size(), x.endian())?) {
+                Some(y) => Val::Time(y),
+                None => return Err(ToValuesError::InvalidValue(self.name().clone())),
+            },
+            Def::Checksum(x) => {
+                let y = match b.get_bytes(x.algo.size()) {
+                    Ok(y) => y,
+                    Err(_) => return Err(ToValuesError::NotEnoughData(self.name().clone())),
+                };
+                Val::VarInt(x.endian.read(y.buf()))
+            }
+            Def::Custom(x) => {
+                let (y, len) = match x.decode(&b.buf()[b.off()..]) {
+                    Ok(y) => y,
+                    Err(e) => return Err(self.codec_error(e)),
+                };
+                if !is_codec_len(x.as_ref(), len, b.cap()) {
+                    return Err(ToValuesError::InvalidValue(self.name().clone()));
+                }
+                b.skip(len).unwrap();
+                y
+            }
+        };
+        // constraints see the raw integer as on encode
+        if let Err(violation) = self.check_constraints(&value) {
+            return Err(ToValuesError::ConstraintViolated {
+                name: self.name().clone(),
+                violation,
+            });
+        }
+        let value = match value {
+            Val::VarInt(x) if self.def().is_int() => self.label(x)?,
+            value => value,
+        };
+        let span = Span {
+            pos,
+            len: b.off() - pos,
+        };
+        let physical = self
+            .scale()
+            .map(|scale| scale.to_physical(value.varint().unwrap()));
+        Ok(ValInfo {
+            value,
+            pos,
+            span,
+            prefix,
+            physical,
+        })
+    }
+
+    fn codec_error(&self, e: CodecError) -> ToValuesError<F> {
+        match e {
+            CodecError::NotEnoughData => ToValuesError::NotEnoughData(self.name().clone()),
+            _ => ToValuesError::InvalidValue(self.name().clone()),
+        }
+    }
+
+    fn get_uint(
+        &self,
+        b: &mut Octets,
+        size: usize,
+        endian: Endian,
+    ) -> Result<u64, ToValuesError<F>> {
+        match b.get_bytes(size) {
+            Ok(x) => Ok(endian.read(x.buf())),
+            Err(_) => Err(ToValuesError::NotEnoughData(self.name().clone())),
+        }
+    }
+
+    fn get_array<const N: usize>(&self, b: &mut Octets) -> Result<[u8; N], ToValuesError<F>> {
+        match b.get_bytes(N) {
+            Ok(x) => Ok(x.buf().try_into().unwrap()),
+            Err(_) => Err(ToValuesError::NotEnoughData(self.name().clone())),
+        }
+    }
+
+    fn label<'buf>(&self, x: u64) -> Result<Val<'buf>, ToValuesError<F>> {
+        let labels = match self.labels() {
+            Some(labels) => labels,
+            None => return Ok(Val::VarInt(x)),
+        };
+        match labels.entry(x) {
+            Some(label) => Ok(Val::Enum(x, label.clone())),
+            None if labels.allows_unknown() => Ok(Val::VarInt(x)),
+            None => Err(ToValuesError::InvalidValue(self.name().clone())),
+        }
+    }
+
+    /// Reads bytes prefixed by a varint length and records the span of the prefix.
+    fn get_var_bytes<'buf>(
+        &self,
+        b: &mut Octets<'buf>,
+        int: TlvInt,
+        prefix: &mut Option<Span>,
+        max_len: Option<usize>,
+    ) -> Result<&'buf [u8], ToValuesError<F>> {
+        let pos = b.off();
+        let len = match int.get(b) {
+            Some(len) => len,
+            None => return Err(ToValuesError::NotEnoughData(self.name().clone())),
+        };
+        // check the claimed length before trusting it
+        self.check_len(len as usize, max_len)?;
+        *prefix = Some(Span {
+            pos,
+            len: b.off() - pos,
+        });
+        match b.get_bytes(len as usize) {
+            Ok(x) => Ok(x.buf()),
+            Err(_) => Err(ToValuesError::NotEnoughData(self.name().clone())),
+        }
+    }
+
+    /// Reads the items of a [`Def::Tlv`] field, with their total length prefix if any.
+    fn get_tlv_bytes<'buf>(
+        &self,
+        x: &Tlv,
+        b: &mut Octets<'buf>,
+        prefix: &mut Option<Span>,
+        max_len: Option<usize>,
+    ) -> Result<&'buf [u8], ToValuesError<F>> {
+        match x.prefix() {
+            Some(int) => self.get_var_bytes(b, int, prefix, max_len),
+            None => {
+                let len = b.cap();
+                self.check_len(len, max_len)?;
+                Ok(b.get_bytes(len).unwrap().buf())
+            }
+        }
+    }
+
+    /// Returns the number of bytes taken by a [`Len::Remaining`] or [`Len::RemainingMinus`] field.
+    fn remaining_len(
+        &self,
+        len: &Len,
+        b: &Octets,
+        max_len: Option<usize>,
+    ) -> Result<usize, ToValuesError<F>> {
+        let trailer = match len {
+            Len::RemainingMinus(trailer) => *trailer,
+            _ => 0,
+        };
+        let len = match b.cap().checked_sub(trailer) {
+            Some(len) => len,
+            None => return Err(ToValuesError::NotEnoughData(self.name().clone())),
+        };
+        self.check_len(len, max_len)?;
+        Ok(len)
+    }
+
+    /// Returns the number of bytes before the next delimiter.
+    fn delimited_len(
+        &self,
+        d: &Delimiter,
+        b: &Octets,
+        max_len: Option<usize>,
+    ) -> Result<usize, ToValuesError<F>> {
+        match d.scan(&b.buf()[b.off()..], min_limit(d.max_len, max_len)) {
+            Scan::Found(len) => Ok(len),
+            Scan::NotFound => Err(ToValuesError::NotEnoughData(self.name().clone())),
+            Scan::TooLong => Err(ToValuesError::LimitExceeded(self.name().clone())),
+        }
+    }
+
+    /// Returns the number of bytes before the next NUL byte.
+    fn nul_pos(&self, b: &Octets, max_len: Option<usize>) -> Result<usize, ToValuesError<F>> {
+        let rest = &b.buf()[b.off()..];
+        // scan no further than one byte past the limit
+        let scanned = match max_len {
+            Some(max_len) => &rest[..rest.len().min(max_len.saturating_add(1))],
+            None => rest,
+        };
+        match scanned.iter().position(|x| *x == 0) {
+            Some(len) => Ok(len),
+            None => {
+                self.check_len(scanned.len(), max_len)?;
+                Err(ToValuesError::NotEnoughData(self.name().clone()))
+            }
+        }
+    }
+
+    fn check_len(&self, len: usize, max_len: Option<usize>) -> Result<(), ToValuesError<F>> {
+        match max_len {
+            Some(max_len) if len > max_len => {
+                Err(ToValuesError::LimitExceeded(self.name().clone()))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Advances `b` past this field without decoding or validating its value.
+    pub fn skip(&self, b: &mut Octets) -> Result<(), ToValuesError<F>> {
+        self.skip_(b, &Limits::default())
+    }
+
+    pub(crate) fn skip_(&self, b: &mut Octets, limits: &Limits) -> Result<(), ToValuesError<F>> {
+        let max_len = min_limit(self.max_len, limits.max_len);
+        let res = match self.def() {
+            Def::VarInt(_) => b.get_varint().map(|_| ()),
+            Def::Bytes(Len::Fixed(len)) => b.skip(*len),
+            Def::Bytes(Len::Var) | Def::Str(StrLen::Var) => {
+                self.get_var_bytes(b, TlvInt::Varint, &mut None, max_len)?;
+                Ok(())
             }
+            Def::Bytes(len @ (Len::Remaining | Len::RemainingMinus(_))) => {
+                let len = self.remaining_len(len, b, max_len)?;
+                b.skip(len)
+            }
+            Def::Bytes(Len::Delimited(d)) => {
+                let len = self.delimited_len(d, b, max_len)?;
+                match d.consume {
+                    true => b.skip(len + d.seq.len()),
+                    false => b.skip(len),
+                }
+            }
+            Def::FixedBytes(x) => b.skip(x.len()),
+            Def::Str(StrLen::Nul) => {
+                let len = self.nul_pos(b, max_len)?;
+                b.skip(len + 1)
+            }
+            Def::Str(StrLen::Padded { len, .. }) => b.skip(*len),
+            Def::Tlv(x) => {
+                self.get_tlv_bytes(x, b, &mut None, max_len)?;
+                Ok(())
+            }
+            Def::Align { to, .. } => b.skip((to - b.off() % to) % to),
+            Def::Padding { len, .. } | Def::Reserved { len, .. } => b.skip(*len),
+            Def::F32(_) => b.skip(4),
+            Def::F64(_) => b.skip(8),
+            Def::FixedPoint(x) => b.skip(x.size()),
+            Def::Bool
+            | Def::Ipv4
+            | Def::Ipv6
+            | Def::Mac
+            | Def::Uuid
+            | Def::Timestamp(_)
+            | Def::Bcd(_)
+            | Def::AsciiNum(_) => b.skip(self.def().fixed_size().unwrap()),
+            Def::Checksum(x) => b.skip(x.algo.size()),
+            Def::Custom(x) => match x.size(&b.buf()[b.off()..]) {
+                Ok(len) if is_codec_len(x.as_ref(), len, b.cap()) => b.skip(len),
+                Ok(_) => return Err(ToValuesError::InvalidValue(self.name().clone())),
+                Err(e) => return Err(self.codec_error(e)),
+            },
+        };
+        match res {
+            Ok(()) => Ok(()),
+            Err(_) => Err(ToValuesError::NotEnoughData(self.name().clone())),
         }
     }
 }
 
+/// Returns whether `len` bytes out of `cap` is a length `x` may report.
+fn is_codec_len(x: &dyn Codec, len: usize, cap: usize) -> bool {
+    len <= cap && x.fixed_size().is_none_or(|size| size == len)
+}
+
 pub enum Def {
     VarInt(U64),
     Bytes(Len),
     FixedBytes(Vec<u8>),
+    /// UTF-8 text
+    Str(StrLen),
+    Tlv(Tlv),
+    /// Fills up to the next multiple of `to` bytes from the start of the block
+    Align {
+        to: usize,
+        fill: u8,
+    },
+    /// Fills `len` bytes
+    Padding {
+        len: usize,
+        fill: u8,
+    },
+    /// Zeros that are rejected on decode if not zero and `check` is set
+    Reserved {
+        len: usize,
+        check: bool,
+    },
+    /// IEEE 754 single precision
+    F32(Endian),
+    /// IEEE 754 double precision; also accepts [`Val::F32`] on encode
+    F64(Endian),
+    /// Accepts [`Val::F32`] and [`Val::F64`] on encode, rounded to the nearest step
+    FixedPoint(FixedPoint),
+    /// A byte that is 0 or 1
+    Bool,
+    Ipv4,
+    Ipv6,
+    /// A 6-byte MAC address
+    Mac,
+    /// A 16-byte UUID in network byte order
+    Uuid,
+    Timestamp(Timestamp),
+    Bcd(Bcd),
+    AsciiNum(AsciiNum),
+    /// Computed on encode and verified on decode; any value provided on encode is ignored
+    Checksum(Checksum),
+    /// An encoding provided outside the crate
+    Custom(Box<dyn Codec>),
+}
+
+impl Def {
+    /// Asserts that the definition is well-formed.
+    pub(crate) fn check_rep(&self) {
+        match self {
+            Def::VarInt(U64::Fixed(x)) => assert!(*x <= MAX_VARINT),
+            Def::Align { to, .. } => assert!(*to > 0),
+            Def::FixedPoint(x) => assert!(x.is_valid()),
+            Def::Timestamp(x) => assert!(x.is_valid()),
+            Def::AsciiNum(x) => assert!(x.is_valid()),
+            _ => (),
+        }
+    }
+
+    /// Returns the encoded size of the field if it does not depend on the value.
+    ///
+    /// Constant varints are assumed to be minimally encoded, as
+    /// [`Block::to_bytes`](crate::Block::to_bytes) writes them.
+    #[must_use]
+    pub fn fixed_size(&self) -> Option<usize> {
+        match self {
+            Def::VarInt(U64::Fixed(x)) => Some(octets::varint_len(*x)),
+            Def::VarInt(U64::Var) => None,
+            Def::Bytes(Len::Fixed(len)) => Some(*len),
+            Def::Bytes(Len::Var | Len::Delimited(_) | Len::Remaining | Len::RemainingMinus(_)) => {
+                None
+            }
+            Def::FixedBytes(x) => Some(x.len()),
+            Def::Str(StrLen::Padded { len, .. }) => Some(*len),
+            Def::Str(StrLen::Nul | StrLen::Var) => None,
+            Def::Tlv(_) => None,
+            Def::Align { .. } => None,
+            Def::Padding { len, .. } | Def::Reserved { len, .. } => Some(*len),
+            Def::F32(_) => Some(4),
+            Def::F64(_) => Some(8),
+            Def::FixedPoint(x) => Some(x.size()),
+            Def::Bool => Some(1),
+            Def::Ipv4 => Some(4),
+            Def::Ipv6 | Def::Uuid => Some(16),
+            Def::Mac => Some(6),
+            Def::Timestamp(x) => Some(x.size()),
+            Def::Bcd(x) => Some(x.len()),
+            Def::AsciiNum(x) => Some(x.len),
+            Def::Checksum(x) => Some(x.algo.size()),
+            Def::Custom(x) => x.fixed_size(),
+        }
+    }
+
+    /// Returns whether the field at the start of `b` takes [`Def::fixed_size`] bytes.
+    ///
+    /// Decoding accepts constant varints in more bytes than the minimum, so
+    /// offsets computed from the fixed sizes only hold if this is true for
+    /// every field before.
+    pub(crate) fn has_fixed_size_in(&self, b: &[u8]) -> bool {
+        match (self, b.first()) {
+            (Def::VarInt(U64::Fixed(x)), Some(first)) => {
+                octets::varint_parse_len(*first) == octets::varint_len(*x)
+            }
+            _ => true,
+        }
+    }
+
+    /// Returns whether the field always encodes the same value, so none needs to be provided.
+    #[must_use]
+    pub fn is_constant(&self) -> bool {
+        matches!(self, Def::VarInt(U64::Fixed(_)) | Def::FixedBytes(_))
+    }
+
+    /// Returns whether the field decodes to an integer that labels and scales apply to.
+    #[must_use]
+    pub fn is_int(&self) -> bool {
+        matches!(self, Def::VarInt(_) | Def::Bcd(_) | Def::AsciiNum(_))
+    }
+
+    /// Returns whether decoded values of the field are reported.
+    ///
+    /// Alignment, padding and reserved fields only shape the layout and any
+    /// value provided for them on encode is ignored.
+    #[must_use]
+    pub fn has_value(&self) -> bool {
+        !matches!(
+            self,
+            Def::Align { .. } | Def::Padding { .. } | Def::Reserved { .. }
+        )
+    }
 }
 
 pub enum U64 {
@@ -191,6 +1063,25 @@ pub enum U64 {
 pub enum Len {
     Fixed(usize),
     Var,
+    /// Runs up to a delimiter
+    Delimited(Delimiter),
+    /// Takes the rest of the buffer, so only the last field of a block may use it
+    Remaining,
+    /// Takes the rest of the buffer except for the given number of trailing bytes
+    ///
+    /// The fields after it must have a fixed size adding up to exactly these
+    /// bytes. Until they do, encoding and decoding the block fail with
+    /// `InvalidValue` naming this field.
+    RemainingMinus(usize),
+}
+
+pub enum StrLen {
+    /// Terminated by a NUL byte that is not part of the value
+    Nul,
+    /// Takes exactly `len` bytes, filled up with `pad` bytes after the value
+    Padded { len: usize, pad: u8 },
+    /// Prefixed by a varint length as in [`Len::Var`]
+    Var,
 }
 
 #[cfg(test)]
@@ -198,6 +1089,7 @@ mod tests {
     use super::*;
 
     #[test]
+    #[allow(clippy::identity_op, clippy::unnecessary_mut_passed)]
     fn test_varint() {
         let field = Field::new(Name::VarInt, Def::VarInt(U64::Var));
         {
@@ -228,6 +1120,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::unnecessary_mut_passed)]
     fn test_fixed_varint() {
         let field = Field::new(Name::FixedVarInt, Def::VarInt(U64::Fixed(0x0102030405)));
         {
@@ -264,6 +1157,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::unnecessary_mut_passed)]
     fn test_bytes_fixed_len() {
         let field = Field::new(Name::BytesFixedLen, Def::Bytes(Len::Fixed(3)));
         {
@@ -297,6 +1191,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::unnecessary_mut_passed)]
     fn test_bytes_var_len() {
         let field = Field::new(Name::BytesVarLen, Def::Bytes(Len::Var));
         {
@@ -328,6 +1223,219 @@ mod tests {
     }
 
     #[test]
+    fn test_bytes_delimited() {
+        let field = Field::new(
+            Name::BytesDelimited,
+            Def::Bytes(Len::Delimited(Delimiter::new(b"\r\n".to_vec()))),
+        );
+        {
+            let mut buf = [0; 4];
+            let mut b = OctetsMut::with_slice(&mut buf);
+            {
+                let vec = b"a\r\n".to_vec();
+                let value = Val::Bytes(&vec);
+                let e = field.to_bytes(Some(&value), &mut b).unwrap_err();
+                assert_eq!(e, ToBytesError::InvalidValue(Name::BytesDelimited));
+            }
+            {
+                let vec = b"abc".to_vec();
+                let value = Val::Bytes(&vec);
+                let e = field.to_bytes(Some(&value), &mut b).unwrap_err();
+                assert_eq!(e, ToBytesError::NotEnoughSpace(Name::BytesDelimited));
+            }
+            let mut buf = [0; 4];
+            let mut b = OctetsMut::with_slice(&mut buf);
+            {
+                let vec = b"ab".to_vec();
+                let value = Val::Bytes(&vec);
+                field.to_bytes(Some(&value), &mut b).unwrap();
+                assert_eq!(buf, *b"ab\r\n");
+            }
+        }
+        {
+            let buf = b"ab\r\ncd".to_vec();
+            let mut b = Octets::with_slice(&buf);
+            let info = field.to_value(&mut b).unwrap();
+            assert_eq!(info.value.bytes().unwrap(), b"ab");
+            assert_eq!(info.span, Span { pos: 0, len: 4 });
+        }
+        {
+            let buf = b"ab\r".to_vec();
+            let mut b = Octets::with_slice(&buf);
+            let e = field.to_value(&mut b).unwrap_err();
+            assert_eq!(e, ToValuesError::NotEnoughData(Name::BytesDelimited));
+        }
+    }
+
+    #[test]
+    fn test_bytes_delimited_options() {
+        let delimiter = Delimiter::new(vec![0xff, 0xff])
+            .included()
+            .with_escape(0xfe)
+            .with_max_len(3);
+        let field = Field::new(Name::BytesDelimited, Def::Bytes(Len::Delimited(delimiter)));
+        {
+            let mut buf = [0; 4];
+            let mut b = OctetsMut::with_slice(&mut buf);
+            {
+                let vec = vec![1, 2];
+                let value = Val::Bytes(&vec);
+                let e = field.to_bytes(Some(&value), &mut b).unwrap_err();
+                assert_eq!(e, ToBytesError::InvalidValue(Name::BytesDelimited));
+            }
+            {
+                let vec = vec![1, 2, 3, 4, 0xff, 0xff];
+                let value = Val::Bytes(&vec);
+                let e = field.to_bytes(Some(&value), &mut b).unwrap_err();
+                assert_eq!(e, ToBytesError::InvalidValue(Name::BytesDelimited));
+            }
+            {
+                let vec = vec![0xfe, 0xff, 0xff, 0xff];
+                let value = Val::Bytes(&vec);
+                field.to_bytes(Some(&value), &mut b).unwrap();
+                assert_eq!(b.off(), 4);
+            }
+        }
+        {
+            let buf = vec![0xfe, 0xff, 0xff, 0xff, 0];
+            let mut b = Octets::with_slice(&buf);
+            let info = field.to_value(&mut b).unwrap();
+            assert_eq!(info.value.bytes().unwrap(), &[0xfe, 0xff, 0xff, 0xff]);
+            assert_eq!(info.span, Span { pos: 0, len: 4 });
+        }
+        {
+            let delimiter = Delimiter::new(vec![0xff]).kept();
+            let field = Field::new(Name::BytesDelimited, Def::Bytes(Len::Delimited(delimiter)));
+            let buf = vec![1, 2, 0xff];
+            let mut b = Octets::with_slice(&buf);
+            let info = field.to_value(&mut b).unwrap();
+            assert_eq!(info.value.bytes().unwrap(), &[1, 2]);
+            assert_eq!(info.span, Span { pos: 0, len: 2 });
+        }
+        {
+            let buf = vec![1, 2, 3, 4, 0xff, 0xff];
+            let mut b = Octets::with_slice(&buf);
+            let e = field.to_value(&mut b).unwrap_err();
+            assert_eq!(e, ToValuesError::LimitExceeded(Name::BytesDelimited));
+        }
+    }
+
+    #[test]
+    fn test_bytes_remaining() {
+        let field = Field::new(Name::BytesRemaining, Def::Bytes(Len::Remaining));
+        {
+            let mut buf = [0; 2];
+            let mut b = OctetsMut::with_slice(&mut buf);
+            {
+                let vec = vec![1, 2, 3];
+                let value = Val::Bytes(&vec);
+                let e = field.to_bytes(Some(&value), &mut b).unwrap_err();
+                assert_eq!(e, ToBytesError::NotEnoughSpace(Name::BytesRemaining));
+            }
+            {
+                let e = field.to_bytes(None, &mut b).unwrap_err();
+                assert_eq!(e, ToBytesError::NoValueProvided(Name::BytesRemaining));
+            }
+        }
+        {
+            let buf = vec![0, 1, 2];
+            let mut b = Octets::with_slice(&buf);
+            b.skip(1).unwrap();
+            let value = field.to_value(&mut b).unwrap().value;
+            assert_eq!(value.bytes().unwrap(), &[1, 2]);
+            assert_eq!(b.cap(), 0);
+        }
+    }
+
+    #[test]
+    fn test_bytes_remaining_minus() {
+        let field = Field::new(Name::BytesRemaining, Def::Bytes(Len::RemainingMinus(2)));
+        {
+            let buf = vec![0, 1, 2, 3];
+            let mut b = Octets::with_slice(&buf);
+            let value = field.to_value(&mut b).unwrap().value;
+            assert_eq!(value.bytes().unwrap(), &[0, 1]);
+            assert_eq!(b.cap(), 2);
+        }
+        {
+            let buf = vec![0];
+            let mut b = Octets::with_slice(&buf);
+            let e = field.to_value(&mut b).unwrap_err();
+            assert_eq!(e, ToValuesError::NotEnoughData(Name::BytesRemaining));
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_align_to_zero() {
+        let _ = Field::new(Name::Align, Def::Align { to: 0, fill: 0 });
+    }
+
+    #[test]
+    fn test_layout() {
+        let align = Field::new(Name::Align, Def::Align { to: 4, fill: 0xff });
+        let padding = Field::new(Name::Padding, Def::Padding { len: 2, fill: 0xee });
+        let reserved = Field::new(
+            Name::Reserved,
+            Def::Reserved {
+                len: 2,
+                check: true,
+            },
+        );
+        {
+            let mut buf = [0; 8];
+            let mut b = OctetsMut::with_slice(&mut buf);
+            b.put_u8(1).unwrap();
+            align.to_bytes(None, &mut b).unwrap();
+            align.to_bytes(None, &mut b).unwrap();
+            padding.to_bytes(None, &mut b).unwrap();
+            let value = Val::VarInt(1);
+            reserved.to_bytes(Some(&value), &mut b).unwrap();
+            assert_eq!(buf, [1, 0xff, 0xff, 0xff, 0xee, 0xee, 0, 0]);
+
+            let mut buf = [0; 1];
+            let mut b = OctetsMut::with_slice(&mut buf);
+            let e = reserved.to_bytes(None, &mut b).unwrap_err();
+            assert_eq!(e, ToBytesError::NotEnoughSpace(Name::Reserved));
+        }
+        {
+            let buf = vec![1, 0, 0, 0, 0xee, 0xee, 0, 0];
+            let mut b = Octets::with_slice(&buf);
+            b.skip(1).unwrap();
+            let info = align.to_value(&mut b).unwrap();
+            assert_eq!(info.span, Span { pos: 1, len: 3 });
+            let info = padding.to_value(&mut b).unwrap();
+            assert_eq!(info.span, Span { pos: 4, len: 2 });
+            reserved.to_value(&mut b).unwrap();
+            assert_eq!(b.cap(), 0);
+        }
+        {
+            let buf = vec![0, 1];
+            let mut b = Octets::with_slice(&buf);
+            let e = reserved.to_value(&mut b).unwrap_err();
+            assert_eq!(e, ToValuesError::InvalidValue(Name::Reserved));
+
+            let reserved = Field::new(
+                Name::Reserved,
+                Def::Reserved {
+                    len: 2,
+                    check: false,
+                },
+            );
+            let mut b = Octets::with_slice(&buf);
+            reserved.to_value(&mut b).unwrap();
+        }
+        {
+            let buf = vec![1, 0];
+            let mut b = Octets::with_slice(&buf);
+            b.skip(1).unwrap();
+            let e = align.to_value(&mut b).unwrap_err();
+            assert_eq!(e, ToValuesError::NotEnoughData(Name::Align));
+        }
+    }
+
+    #[test]
+    #[allow(clippy::unnecessary_mut_passed)]
     fn test_fixed_bytes() {
         let field = Field::new(Name::FixedBytes, Def::FixedBytes(vec![1, 2, 3]));
         {
@@ -371,13 +1479,244 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_str_nul() {
+        let field = Field::new(Name::Str, Def::Str(StrLen::Nul));
+        {
+            let mut buf = [0; 4];
+            let mut b = OctetsMut::with_slice(&mut buf);
+            {
+                let value = Val::Str("a\0b");
+                let e = field.to_bytes(Some(&value), &mut b).unwrap_err();
+                assert_eq!(e, ToBytesError::InvalidValue(Name::Str));
+            }
+            {
+                let value = Val::Str("abcd");
+                let e = field.to_bytes(Some(&value), &mut b).unwrap_err();
+                assert_eq!(e, ToBytesError::NotEnoughSpace(Name::Str));
+            }
+            let mut buf = [0; 4];
+            let mut b = OctetsMut::with_slice(&mut buf);
+            {
+                let value = Val::Str("abc");
+                field.to_bytes(Some(&value), &mut b).unwrap();
+                assert_eq!(buf, *b"abc\0");
+            }
+        }
+        {
+            let buf = b"abc\0d".to_vec();
+            let mut b = Octets::with_slice(&buf);
+            let info = field.to_value(&mut b).unwrap();
+            assert_eq!(info.value.str().unwrap(), "abc");
+            assert_eq!(info.span, Span { pos: 0, len: 4 });
+        }
+        {
+            let buf = b"abc".to_vec();
+            let mut b = Octets::with_slice(&buf);
+            let e = field.to_value(&mut b).unwrap_err();
+            assert_eq!(e, ToValuesError::NotEnoughData(Name::Str));
+        }
+        {
+            let buf = vec![0xff, 0];
+            let mut b = Octets::with_slice(&buf);
+            let e = field.to_value(&mut b).unwrap_err();
+            assert_eq!(e, ToValuesError::InvalidUtf8(Name::Str));
+        }
+    }
+
+    #[test]
+    fn test_str_padded() {
+        let field = Field::new(Name::Str, Def::Str(StrLen::Padded { len: 4, pad: b' ' }));
+        {
+            let mut buf = [0; 4];
+            let mut b = OctetsMut::with_slice(&mut buf);
+            {
+                let value = Val::Str("abcde");
+                let e = field.to_bytes(Some(&value), &mut b).unwrap_err();
+                assert_eq!(e, ToBytesError::InvalidValue(Name::Str));
+            }
+            {
+                let value = Val::Str("ab ");
+                let e = field.to_bytes(Some(&value), &mut b).unwrap_err();
+                assert_eq!(e, ToBytesError::InvalidValue(Name::Str));
+            }
+            {
+                let value = Val::VarInt(1);
+                let e = field.to_bytes(Some(&value), &mut b).unwrap_err();
+                assert_eq!(e, ToBytesError::InvalidValue(Name::Str));
+            }
+            {
+                let value = Val::Str("ab");
+                field.to_bytes(Some(&value), &mut b).unwrap();
+                assert_eq!(buf, *b"ab  ");
+            }
+            let mut buf = [0; 3];
+            let mut b = OctetsMut::with_slice(&mut buf);
+            {
+                let value = Val::Str("ab");
+                let e = field.to_bytes(Some(&value), &mut b).unwrap_err();
+                assert_eq!(e, ToBytesError::NotEnoughSpace(Name::Str));
+            }
+        }
+        {
+            let buf = b"ab  ".to_vec();
+            let mut b = Octets::with_slice(&buf);
+            let value = field.to_value(&mut b).unwrap().value;
+            assert_eq!(value.str().unwrap(), "ab");
+        }
+        {
+            let buf = b"    ".to_vec();
+            let mut b = Octets::with_slice(&buf);
+            let value = field.to_value(&mut b).unwrap().value;
+            assert_eq!(value.str().unwrap(), "");
+        }
+    }
+
+    #[test]
+    fn test_str_var() {
+        let field = Field::new(Name::Str, Def::Str(StrLen::Var));
+        {
+            let mut buf = [0; 4];
+            let mut b = OctetsMut::with_slice(&mut buf);
+            {
+                let e = field.to_bytes(None, &mut b).unwrap_err();
+                assert_eq!(e, ToBytesError::NoValueProvided(Name::Str));
+            }
+            {
+                let value = Val::Str("abc");
+                field.to_bytes(Some(&value), &mut b).unwrap();
+                assert_eq!(buf, *b"\x03abc");
+            }
+        }
+        {
+            let buf = vec![2, 0xc3, 0xa9];
+            let mut b = Octets::with_slice(&buf);
+            let info = field.to_value(&mut b).unwrap();
+            assert_eq!(info.value.str().unwrap(), "é");
+            assert_eq!(info.prefix, Some(Span { pos: 0, len: 1 }));
+        }
+        {
+            let buf = vec![2, 0xc3, 0x28];
+            let mut b = Octets::with_slice(&buf);
+            let e = field.to_value(&mut b).unwrap_err();
+            assert_eq!(e, ToValuesError::InvalidUtf8(Name::Str));
+        }
+    }
+
+    #[test]
+    fn test_default() {
+        let mut field = Field::new(Name::VarInt, Def::VarInt(U64::Var));
+        assert!(!field.has_default());
+        assert!(!field.def().is_constant());
+        field.set_default(Val::VarInt(7));
+        assert!(field.has_default());
+        {
+            let mut buf = [0; 2];
+            let mut b = OctetsMut::with_slice(&mut buf);
+            field.to_bytes(None, &mut b).unwrap();
+            field.to_bytes(Some(&Val::VarInt(8)), &mut b).unwrap();
+            assert_eq!(buf, [7, 8]);
+        }
+
+        let counter = std::sync::atomic::AtomicU64::new(0);
+        let mut field = Field::new(Name::Str, Def::Str(StrLen::Var));
+        field.set_default_with(move || {
+            let n = counter.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            Val::Str(if n == 0 { "a" } else { "bc" })
+        });
+        field.add_constraint(Constraint::Len(0..=1));
+        {
+            let mut buf = [0; 4];
+            let mut b = OctetsMut::with_slice(&mut buf);
+            field.to_bytes(None, &mut b).unwrap();
+            assert_eq!(&buf[..2], &[1, b'a']);
+            let mut b = OctetsMut::with_slice(&mut buf);
+            let e = field.to_bytes(None, &mut b).unwrap_err();
+            assert!(matches!(e, ToBytesError::ConstraintViolated { .. }));
+        }
+
+        let field = Field::new(Name::FixedBytes, Def::FixedBytes(vec![1]));
+        assert!(!field.has_default());
+        assert!(field.def().is_constant());
+    }
+
+    #[test]
+    fn test_semantic() {
+        let field = Field::new(Name::Bool, Def::Bool);
+        {
+            let mut buf = [0; 1];
+            let mut b = OctetsMut::with_slice(&mut buf);
+            field.to_bytes(Some(&Val::Bool(true)), &mut b).unwrap();
+            assert_eq!(buf, [1]);
+            let mut b = OctetsMut::with_slice(&mut buf);
+            let e = field.to_bytes(Some(&Val::VarInt(1)), &mut b).unwrap_err();
+            assert_eq!(e, ToBytesError::InvalidValue(Name::Bool));
+        }
+        {
+            let mut b = Octets::with_slice(&[0]);
+            assert_eq!(field.to_value(&mut b).unwrap().value, Val::Bool(false));
+            let mut b = Octets::with_slice(&[2]);
+            let e = field.to_value(&mut b).unwrap_err();
+            assert_eq!(e, ToValuesError::InvalidValue(Name::Bool));
+        }
+
+        let values = [
+            (Def::Ipv4, Val::Ipv4(Ipv4Addr::new(192, 0, 2, 1))),
+            (Def::Ipv6, Val::Ipv6(Ipv6Addr::LOCALHOST)),
+            (Def::Mac, Val::Mac([0, 0x1b, 0x63, 0x84, 0x45, 0xe6])),
+            (Def::Uuid, Val::Uuid([0xab; 16])),
+            (
+                Def::Timestamp(Timestamp::Unix {
+                    unit: crate::TimeUnit::Secs,
+                    size: 4,
+                    endian: Endian::Little,
+                }),
+                Val::Time(std::time::UNIX_EPOCH + std::time::Duration::from_secs(0x01020304)),
+            ),
+        ];
+        for (def, value) in values {
+            let size = def.fixed_size().unwrap();
+            let field = Field::new(Name::Semantic, def);
+            let mut buf = [0; 16];
+            let mut b = OctetsMut::with_slice(&mut buf);
+            field.to_bytes(Some(&value), &mut b).unwrap();
+            assert_eq!(b.off(), size);
+            let mut b = Octets::with_slice(&buf[..size]);
+            assert_eq!(field.to_value(&mut b).unwrap().value, value);
+            let mut b = Octets::with_slice(&buf[..size - 1]);
+            let e = field.to_value(&mut b).unwrap_err();
+            assert_eq!(e, ToValuesError::NotEnoughData(Name::Semantic));
+        }
+        {
+            let field = Field::new(Name::Semantic, Def::Ipv4);
+            let mut buf = [0; 4];
+            let mut b = OctetsMut::with_slice(&mut buf);
+            field
+                .to_bytes(Some(&Val::Ipv4(Ipv4Addr::new(192, 0, 2, 1))), &mut b)
+                .unwrap();
+            assert_eq!(buf, [192, 0, 2, 1]);
+            let mut b = OctetsMut::with_slice(&mut buf);
+            let value = Val::Ipv6(Ipv6Addr::LOCALHOST);
+            let e = field.to_bytes(Some(&value), &mut b).unwrap_err();
+            assert_eq!(e, ToBytesError::InvalidValue(Name::Semantic));
+        }
+    }
+
     #[derive(Debug, PartialEq, Eq, Hash, Clone)]
     enum Name {
+        Bool,
+        Semantic,
         FixedVarInt,
         VarInt,
         BytesFixedLen,
         BytesVarLen,
+        BytesDelimited,
+        BytesRemaining,
         FixedBytes,
+        Str,
+        Align,
+        Padding,
+        Reserved,
     }
 
     impl FieldName for Name {}
@@ -0,0 +1,208 @@
+use std::borrow::Cow;
+
+/// A table of symbolic names for the values of an integer field.
+///
+/// Attached with [`Field::set_labels`](crate::Field::set_labels). Known values
+/// decode to [`Val::Enum`](crate::Val::Enum), and a field with labels accepts
+/// a label as [`Val::Str`](crate::Val::Str) on encode.
+pub struct Labels {
+    entries: Vec<(u64, Cow<'static, str>)>,
+    allow_unknown: bool,
+}
+
+impl Labels {
+    /// Returns a table that rejects unknown values.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a value or a label appears more than once.
+    #[must_use]
+    pub fn new<L>(entries: Vec<(u64, L)>) -> Self
+    where
+        L: Into<Cow<'static, str>>,
+    {
+        let entries: Vec<_> = entries
+            .into_iter()
+            .map(|(value, label)| (value, label.into()))
+            .collect();
+        for (i, (value, label)) in entries.iter().enumerate() {
+            assert!(entries[..i].iter().all(|(x, y)| x != value && y != label));
+        }
+        Labels {
+            entries,
+            allow_unknown: false,
+        }
+    }
+
+    /// Accepts values not in the table, which decode to [`Val::VarInt`](crate::Val::VarInt).
+    #[must_use]
+    pub fn allow_unknown(mut self) -> Self {
+        self.allow_unknown = true;
+        self
+    }
+
+    #[must_use]
+    pub fn allows_unknown(&self) -> bool {
+        self.allow_unknown
+    }
+
+    #[must_use]
+    pub fn label(&self, value: u64) -> Option<&str> {
+        self.entry(value).map(|label| label.as_ref())
+    }
+
+    /// Returns the label of `value` as stored, which is cheap to clone when static.
+    pub(crate) fn entry(&self, value: u64) -> Option<&Cow<'static, str>> {
+        self.entries
+            .iter()
+            .find(|(x, _)| *x == value)
+            .map(|(_, label)| label)
+    }
+
+    #[must_use]
+    pub fn value(&self, label: &str) -> Option<u64> {
+        self.entries
+            .iter()
+            .find(|(_, x)| *x == label)
+            .map(|(value, _)| *value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::{
+        Block, Constraint, Def, FieldName, ToBytesError, ToValuesError, Val, Violation, U64,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_lookup() {
+        let labels = get_labels();
+        assert_eq!(labels.label(0x05), Some("Pong"));
+        assert_eq!(labels.label(0x02), None);
+        assert_eq!(labels.value("Ping"), Some(0x01));
+        assert_eq!(labels.value("Reset"), None);
+        assert!(!labels.allows_unknown());
+
+        let labels = Labels::new(vec![(3, format!("Ping{}", 2))]).allow_unknown();
+        assert_eq!(labels.label(3), Some("Ping2"));
+        assert!(labels.allows_unknown());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_duplicate() {
+        let _ = Labels::new(vec![(1, "Ping"), (2, "Ping")]);
+    }
+
+    #[test]
+    fn test_block() {
+        let mut block = Block::new();
+        block
+            .add_field(Name::Type, Def::VarInt(U64::Var))
+            .set_labels(get_labels());
+
+        let mut buf = [0; 8];
+        let mut values = HashMap::new();
+        values.insert(Name::Type, Val::Str("Pong"));
+        let end = block.to_bytes(&values, &mut buf).unwrap();
+        assert_eq!(&buf[..end], &[0x05]);
+        values.insert(Name::Type, Val::VarInt(0x09));
+        let end = block.to_bytes(&values, &mut buf).unwrap();
+        assert_eq!(&buf[..end], &[0x09]);
+        values.insert(Name::Type, Val::Enum(0x01, "Ping".into()));
+        let end = block.to_bytes(&values, &mut buf).unwrap();
+        assert_eq!(&buf[..end], &[0x01]);
+
+        values.insert(Name::Type, Val::Str("Reset"));
+        let e = block.to_bytes(&values, &mut buf).unwrap_err();
+        assert_eq!(e, ToBytesError::InvalidValue(Name::Type));
+        values.insert(Name::Type, Val::VarInt(0x02));
+        let e = block.to_bytes(&values, &mut buf).unwrap_err();
+        assert_eq!(e, ToBytesError::InvalidValue(Name::Type));
+        values.insert(Name::Type, Val::Enum(0x01, "Close".into()));
+        let e = block.to_bytes(&values, &mut buf).unwrap_err();
+        assert_eq!(e, ToBytesError::InvalidValue(Name::Type));
+
+        let mut values = HashMap::new();
+        block.to_values(&[0x09], &mut values).unwrap();
+        let value = &values[&Name::Type].value;
+        assert_eq!(value, &Val::Enum(0x09, "Close".into()));
+        assert_eq!(value.label().unwrap(), "Close");
+        assert_eq!(value.varint().unwrap(), 0x09);
+        assert_eq!(value.to_string(), "Close (9)");
+
+        let e = block.to_values(&[0x02], &mut values).unwrap_err();
+        assert_eq!(e, ToValuesError::InvalidValue(Name::Type));
+
+        let mut block = Block::new();
+        block
+            .add_field(Name::Type, Def::VarInt(U64::Fixed(0x09)))
+            .set_labels(get_labels());
+        let mut values = HashMap::new();
+        block.compile().to_values(&[0x09], &mut values).unwrap();
+        assert_eq!(values[&Name::Type].value, Val::Enum(0x09, "Close".into()));
+    }
+
+    #[test]
+    fn test_constraints() {
+        let mut block = Block::new();
+        block
+            .add_field(Name::Type, Def::VarInt(U64::Var))
+            .set_labels(get_labels())
+            .add_constraint(Constraint::OneOf(vec![0x01, 0x05]))
+            .add_constraint(Constraint::custom("raw", |x| matches!(x, Val::VarInt(_))));
+
+        let mut buf = [0; 8];
+        let mut values = HashMap::new();
+        values.insert(Name::Type, Val::Enum(0x09, "Close".into()));
+        let e = block.to_bytes(&values, &mut buf).unwrap_err();
+        assert!(matches!(e, ToBytesError::ConstraintViolated { .. }));
+
+        let mut values = HashMap::new();
+        let e = block.to_values(&[0x09], &mut values).unwrap_err();
+        assert_eq!(
+            e,
+            ToValuesError::ConstraintViolated {
+                name: Name::Type,
+                violation: Violation::NotOneOf(0x09)
+            }
+        );
+        block.to_values(&[0x05], &mut values).unwrap();
+        assert_eq!(values[&Name::Type].value, Val::Enum(0x05, "Pong".into()));
+    }
+
+    #[test]
+    fn test_unknown() {
+        let mut block = Block::new();
+        block
+            .add_field(Name::Type, Def::VarInt(U64::Var))
+            .set_labels(get_labels().allow_unknown());
+
+        let mut buf = [0; 8];
+        let mut values = HashMap::new();
+        values.insert(Name::Type, Val::VarInt(0x02));
+        let end = block.to_bytes(&values, &mut buf).unwrap();
+        assert_eq!(&buf[..end], &[0x02]);
+
+        let mut values = HashMap::new();
+        block.to_values(&[0x02], &mut values).unwrap();
+        let value = &values[&Name::Type].value;
+        assert_eq!(value, &Val::VarInt(0x02));
+        assert!(value.label().is_err());
+    }
+
+    fn get_labels() -> Labels {
+        Labels::new(vec![(0x01, "Ping"), (0x05, "Pong"), (0x09, "Close")])
+    }
+
+    #[derive(Debug, PartialEq, Eq, Hash, Clone)]
+    enum Name {
+        Type,
+    }
+
+    impl FieldName for Name {}
+}
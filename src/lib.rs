@@ -1,14 +1,49 @@
 mod block;
+mod checksum;
+mod codec;
+mod constraint;
+mod decimal;
+mod delimiter;
+mod dump;
 mod field;
+mod labels;
+mod limits;
+mod num;
+mod plan;
+#[cfg(feature = "serde")]
+mod serialize;
+mod sink;
+mod time;
+mod tlv;
 mod value;
+mod view;
 
-use std::hash::Hash;
+use std::fmt;
 
 pub use block::*;
+pub use checksum::*;
+pub use codec::*;
+pub use constraint::*;
+pub use decimal::*;
+pub use delimiter::*;
+pub use dump::*;
 pub use field::*;
+pub use labels::*;
+pub use limits::*;
+pub use num::*;
+pub use plan::*;
+#[cfg(feature = "serde")]
+pub use serialize::*;
+pub use sink::*;
+pub use time::*;
+pub use tlv::*;
 pub use value::*;
+pub use view::*;
 
-pub trait FieldName: PartialEq + Eq + Hash + Clone {}
+pub trait FieldName: PartialEq + Eq + Clone {}
+
+/// Names read at runtime, such as from a schema file
+impl FieldName for String {}
 
 #[derive(Debug, PartialEq)]
 pub enum ToBytesError<F>
@@ -18,6 +53,25 @@ where
     NoValueProvided(F),
     InvalidValue(F),
     NotEnoughSpace(F),
+    /// The value does not fit the field
+    ///
+    /// Either the physical value has no raw value under the [`Scale`] of the
+    /// field, or the raw value, TLV type or TLV length is too large for the
+    /// width of its encoding.
+    OutOfRange(F),
+    /// The value of `name` breaks a constraint added by [`Field::add_constraint`], as told by `violation`
+    ConstraintViolated {
+        name: F,
+        violation: Violation,
+    },
+    /// A field of the block that items of type `ty` of the [`Tlv`] field `name` encode with failed
+    ///
+    /// The inner error names the field in the block by its `Debug` form.
+    Nested {
+        name: F,
+        ty: u64,
+        error: Box<ToBytesError<String>>,
+    },
 }
 
 #[derive(Debug, PartialEq)]
@@ -27,6 +81,161 @@ where
 {
     InvalidValue(F),
     NotEnoughData(F),
+    InvalidUtf8(F),
+    /// The decoded value of `name` breaks one of its constraints, as told by `violation`
+    ConstraintViolated {
+        name: F,
+        violation: Violation,
+    },
+    /// The field is over a limit set by [`Field::set_max_len`] or [`Limits`]
+    LimitExceeded(F),
+    /// `expected` is computed over the data and `actual` is read from the checksum field
+    ChecksumMismatch {
+        name: F,
+        expected: u64,
+        actual: u64,
+    },
+    /// A field of the block that items of type `ty` of the [`Tlv`] field `name` decode with failed
+    ///
+    /// The inner error names the field in the block by its `Debug` form.
+    Nested {
+        name: F,
+        ty: u64,
+        error: Box<ToValuesError<String>>,
+    },
+}
+
+impl<F> ToBytesError<F>
+where
+    F: FieldName,
+{
+    /// Returns the same error for the field named `name`.
+    pub(crate) fn with_name<G>(self, name: G) -> ToBytesError<G>
+    where
+        G: FieldName,
+    {
+        self.map_name(|_| name)
+    }
+
+    /// Returns the same error with the name of the field mapped by `f`.
+    pub(crate) fn map_name<G>(self, f: impl FnOnce(F) -> G) -> ToBytesError<G>
+    where
+        G: FieldName,
+    {
+        match self {
+            ToBytesError::NoValueProvided(name) => ToBytesError::NoValueProvided(f(name)),
+            ToBytesError::InvalidValue(name) => ToBytesError::InvalidValue(f(name)),
+            ToBytesError::NotEnoughSpace(name) => ToBytesError::NotEnoughSpace(f(name)),
+            ToBytesError::OutOfRange(name) => ToBytesError::OutOfRange(f(name)),
+            ToBytesError::ConstraintViolated { name, violation } => {
+                ToBytesError::ConstraintViolated {
+                    name: f(name),
+                    violation,
+                }
+            }
+            ToBytesError::Nested { name, ty, error } => ToBytesError::Nested {
+                name: f(name),
+                ty,
+                error,
+            },
+        }
+    }
+}
+
+impl<F> ToValuesError<F>
+where
+    F: FieldName,
+{
+    /// Returns the same error for the field named `name`.
+    pub(crate) fn with_name<G>(self, name: G) -> ToValuesError<G>
+    where
+        G: FieldName,
+    {
+        self.map_name(|_| name)
+    }
+
+    /// Returns the same error with the name of the field mapped by `f`.
+    pub(crate) fn map_name<G>(self, f: impl FnOnce(F) -> G) -> ToValuesError<G>
+    where
+        G: FieldName,
+    {
+        match self {
+            ToValuesError::InvalidValue(name) => ToValuesError::InvalidValue(f(name)),
+            ToValuesError::NotEnoughData(name) => ToValuesError::NotEnoughData(f(name)),
+            ToValuesError::InvalidUtf8(name) => ToValuesError::InvalidUtf8(f(name)),
+            ToValuesError::LimitExceeded(name) => ToValuesError::LimitExceeded(f(name)),
+            ToValuesError::ConstraintViolated { name, violation } => {
+                ToValuesError::ConstraintViolated {
+                    name: f(name),
+                    violation,
+                }
+            }
+            ToValuesError::ChecksumMismatch {
+                name,
+                expected,
+                actual,
+            } => ToValuesError::ChecksumMismatch {
+                name: f(name),
+                expected,
+                actual,
+            },
+            ToValuesError::Nested { name, ty, error } => ToValuesError::Nested {
+                name: f(name),
+                ty,
+                error,
+            },
+        }
+    }
+}
+
+/// Names the field by its `Debug` form.
+impl<F> fmt::Display for ToBytesError<F>
+where
+    F: FieldName + fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ToBytesError::NoValueProvided(name) => write!(f, "no value provided for {name:?}"),
+            ToBytesError::InvalidValue(name) => write!(f, "invalid value for {name:?}"),
+            ToBytesError::NotEnoughSpace(name) => write!(f, "not enough space for {name:?}"),
+            ToBytesError::OutOfRange(name) => write!(f, "value of {name:?} is out of range"),
+            ToBytesError::ConstraintViolated { name, violation } => {
+                write!(f, "value of {name:?} breaks a constraint: {violation}")
+            }
+            ToBytesError::Nested { name, ty, error } => {
+                write!(f, "item of type {ty} in {name:?}: {error}")
+            }
+        }
+    }
+}
+
+/// Names the field by its `Debug` form.
+impl<F> fmt::Display for ToValuesError<F>
+where
+    F: FieldName + fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ToValuesError::InvalidValue(name) => write!(f, "invalid value for {name:?}"),
+            ToValuesError::NotEnoughData(name) => write!(f, "not enough data for {name:?}"),
+            ToValuesError::InvalidUtf8(name) => write!(f, "invalid UTF-8 in {name:?}"),
+            ToValuesError::ConstraintViolated { name, violation } => {
+                write!(f, "value of {name:?} breaks a constraint: {violation}")
+            }
+            ToValuesError::LimitExceeded(name) => write!(f, "{name:?} is over its limit"),
+            ToValuesError::ChecksumMismatch {
+                name,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "checksum {name:?} is {actual:#x}, expected {expected:#x}"
+            ),
+            ToValuesError::Nested { name, ty, error } => {
+                write!(f, "item of type {ty} in {name:?}: {error}")
+            }
+        }
+    }
 }
 
 #[cfg(test)]
@@ -36,6 +245,7 @@ mod tests {
     use super::*;
 
     #[test]
+    #[allow(clippy::identity_op)]
     fn test_to_bytes() {
         let block = get_block();
 
@@ -84,6 +294,38 @@ mod tests {
     }
 
     #[test]
+    fn test_to_bytes_with_spans() {
+        let block = get_block();
+
+        let mut values = HashMap::new();
+        values.insert(Name::VarInt, Val::VarInt(0x1234));
+        let vec = vec![1];
+        values.insert(Name::BytesFixedLen, Val::Bytes(&vec));
+        let vec = vec![1, 2, 3];
+        values.insert(Name::BytesVarLen, Val::Bytes(&vec));
+
+        let mut vec = vec![0; 1024];
+        let mut spans = HashMap::new();
+
+        let end = block
+            .to_bytes_with_spans(&values, &mut vec, &mut spans)
+            .unwrap();
+
+        assert_eq!(end, 19);
+        assert_eq!(spans.len(), 5);
+        assert_eq!(spans[&Name::FixedVarInt], Span { pos: 0, len: 8 });
+        assert_eq!(spans[&Name::VarInt], Span { pos: 8, len: 2 });
+        assert_eq!(spans[&Name::BytesFixedLen], Span { pos: 10, len: 1 });
+        assert_eq!(spans[&Name::BytesVarLen], Span { pos: 11, len: 4 });
+        assert_eq!(spans[&Name::FixedBytes], Span { pos: 15, len: 4 });
+        assert_eq!(
+            &vec[spans[&Name::FixedBytes].range()],
+            &[0xba, 0xad, 0xf0, 0x0d]
+        );
+    }
+
+    #[test]
+    #[allow(clippy::identity_op)]
     fn test_to_values() {
         let block = get_block();
 
@@ -120,17 +362,187 @@ mod tests {
 
         assert_eq!(end, vec.len());
 
-        let ValInfo { value, pos } = values.get(&Name::VarInt).unwrap();
+        let ValInfo { value, pos, .. } = values.get(&Name::VarInt).unwrap();
         assert_eq!(value.varint().unwrap(), 0x1234);
         assert_eq!(*pos, 8);
 
-        let ValInfo { value, pos } = values.get(&Name::BytesFixedLen).unwrap();
+        let ValInfo { value, pos, .. } = values.get(&Name::BytesFixedLen).unwrap();
         assert_eq!(value.bytes().unwrap(), vec![1]);
         assert_eq!(*pos, 10);
 
-        let ValInfo { value, pos } = values.get(&Name::BytesVarLen).unwrap();
+        let info = values.get(&Name::BytesVarLen).unwrap();
+        assert_eq!(info.value.bytes().unwrap(), vec![1, 2, 3]);
+        assert_eq!(info.pos, 11);
+        assert_eq!(info.span, Span { pos: 11, len: 4 });
+        assert_eq!(info.prefix, Some(Span { pos: 11, len: 1 }));
+        assert_eq!(&vec[info.payload().range()], &[1, 2, 3]);
+
+        let info = values.get(&Name::FixedVarInt).unwrap();
+        assert_eq!(info.span, Span { pos: 0, len: 8 });
+        assert_eq!(info.prefix, None);
+    }
+
+    #[test]
+    fn test_to_selected_values() {
+        let block = get_block();
+
+        let vec = vec![
+            // fixed varint
+            0xc0,
+            0,
+            0,
+            0,
+            0xde,
+            0xad,
+            0xbe,
+            0xef,
+            // varint
+            0x12 | 0x40,
+            0x34,
+            // bytes with fixed len
+            1,
+            // bytes with var len
+            3,
+            1,
+            2,
+            3,
+            // fixed bytes missing
+        ];
+
+        let mut values = HashMap::new();
+
+        let names = [Name::BytesVarLen, Name::VarInt];
+        let end = block.to_selected_values(&vec, &names, &mut values).unwrap();
+
+        assert_eq!(end, vec.len());
+        assert_eq!(values.len(), 2);
+
+        let ValInfo { value, pos, .. } = values.get(&Name::VarInt).unwrap();
+        assert_eq!(value.varint().unwrap(), 0x1234);
+        assert_eq!(*pos, 8);
+
+        let ValInfo { value, pos, .. } = values.get(&Name::BytesVarLen).unwrap();
         assert_eq!(value.bytes().unwrap(), vec![1, 2, 3]);
         assert_eq!(*pos, 11);
+
+        let mut values = HashMap::new();
+
+        let names = [Name::FixedBytes];
+        let e = block
+            .to_selected_values(&vec, &names, &mut values)
+            .unwrap_err();
+
+        assert_eq!(e, ToValuesError::NotEnoughData(Name::FixedBytes));
+    }
+
+    #[test]
+    fn test_layout_fields() {
+        let mut block = Block::new();
+        block.add_field(Name::VarInt, Def::VarInt(U64::Var));
+        block.add_field(Name::Align, Def::Align { to: 4, fill: 0 });
+        block.add_field(Name::BytesFixedLen, Def::Bytes(Len::Fixed(1)));
+        block.add_field(
+            Name::Reserved,
+            Def::Reserved {
+                len: 3,
+                check: true,
+            },
+        );
+
+        let mut values = HashMap::new();
+        values.insert(Name::VarInt, Val::VarInt(1));
+        let vec = vec![2];
+        values.insert(Name::BytesFixedLen, Val::Bytes(&vec));
+
+        let mut vec = vec![0xff; 1024];
+
+        let end = block.to_bytes(&values, &mut vec).unwrap();
+
+        assert_eq!(&vec[..end], &[1, 0, 0, 0, 2, 0, 0, 0]);
+
+        let mut values = HashMap::new();
+
+        let end = block.to_values(&vec[..end], &mut values).unwrap();
+
+        assert_eq!(end, 8);
+        assert_eq!(values.len(), 2);
+        assert_eq!(values[&Name::BytesFixedLen].pos, 4);
+        assert!(!values.contains_key(&Name::Align));
+
+        let mut view = block.view(&vec);
+        assert!(view.get(&Name::Reserved).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_error_display() {
+        let e = ToBytesError::ConstraintViolated {
+            name: Name::VarInt,
+            violation: Violation::OutOfRange {
+                min: 1,
+                max: 3,
+                actual: 4,
+            },
+        };
+        assert_eq!(
+            e.to_string(),
+            "value of VarInt breaks a constraint: 4 is not in 1..=3"
+        );
+        let e = ToBytesError::Nested {
+            name: Name::VarInt,
+            ty: 2,
+            error: Box::new(ToBytesError::NoValueProvided("Version".to_string())),
+        };
+        assert_eq!(
+            e.to_string(),
+            "item of type 2 in VarInt: no value provided for \"Version\""
+        );
+        let e = ToValuesError::ChecksumMismatch {
+            name: Name::VarInt,
+            expected: 0x1234,
+            actual: 0xabcd,
+        };
+        assert_eq!(e.to_string(), "checksum VarInt is 0xabcd, expected 0x1234");
+        let e = ToValuesError::NotEnoughData(Name::BytesVarLen);
+        assert_eq!(e.to_string(), "not enough data for BytesVarLen");
+    }
+
+    #[test]
+    fn test_introspection() {
+        let block = get_block();
+        let names: Vec<_> = block.fields().iter().map(|field| field.name()).collect();
+        assert_eq!(
+            names,
+            [
+                &Name::FixedVarInt,
+                &Name::VarInt,
+                &Name::BytesFixedLen,
+                &Name::BytesVarLen,
+                &Name::FixedBytes
+            ]
+        );
+        let field = block.field(&Name::BytesFixedLen).unwrap();
+        assert!(matches!(field.def(), Def::Bytes(Len::Fixed(1))));
+        assert_eq!(field.def().fixed_size(), Some(1));
+        assert!(block.field(&Name::Align).is_none());
+
+        let offsets: Vec<_> = block.fixed_offsets().collect();
+        assert_eq!(offsets, [Some(0), Some(8), None, None, None]);
+        assert_eq!(block.fixed_offset(&Name::VarInt), Some(8));
+        assert_eq!(block.fixed_offset(&Name::FixedBytes), None);
+        assert_eq!(block.fixed_offset(&Name::Align), None);
+        assert_eq!(block.fixed_size(), None);
+
+        let mut block = Block::new();
+        assert_eq!(block.fixed_size(), Some(0));
+        block
+            .add_field(Name::BytesFixedLen, Def::Bytes(Len::Fixed(1)))
+            .add_constraint(Constraint::Len(1..=1));
+        block.add_field(Name::Align, Def::Align { to: 4, fill: 0 });
+        block.add_field(Name::FixedBytes, Def::FixedBytes(vec![1, 2]));
+        let offsets: Vec<_> = block.fixed_offsets().collect();
+        assert_eq!(offsets, [Some(0), Some(1), Some(4)]);
+        assert_eq!(block.fixed_size(), Some(6));
+        assert_eq!(block.fields()[0].constraints().len(), 1);
     }
 
     fn get_block() -> Block<Name> {
@@ -153,6 +565,8 @@ mod tests {
         BytesFixedLen,
         BytesVarLen,
         FixedBytes,
+        Align,
+        Reserved,
     }
 
     impl FieldName for Name {}
@@ -0,0 +1,145 @@
+/// Bounds enforced on decode to guard against hostile input.
+///
+/// A field over a limit fails with
+/// [`ToValuesError::LimitExceeded`](crate::ToValuesError::LimitExceeded).
+#[derive(Debug, Clone, Default)]
+pub struct Limits {
+    /// The maximum length of the value of any variable-length bytes or string field
+    pub max_len: Option<usize>,
+    /// The maximum number of bytes the whole block may take
+    pub max_size: Option<usize>,
+    /// The maximum number of items in any [`Tlv`](crate::Tlv)
+    pub max_count: Option<usize>,
+    /// The maximum nesting of [`Tlv`](crate::Tlv) fields
+    pub max_depth: Option<usize>,
+}
+
+impl Limits {
+    /// Returns the tighter of each limit in `self` and `other`.
+    pub(crate) fn tighter(&self, other: &Limits) -> Limits {
+        Limits {
+            max_len: min_limit(self.max_len, other.max_len),
+            max_size: min_limit(self.max_size, other.max_size),
+            max_count: min_limit(self.max_count, other.max_count),
+            max_depth: min_limit(self.max_depth, other.max_depth),
+        }
+    }
+}
+
+/// Returns the tighter of two optional limits.
+pub(crate) fn min_limit(a: Option<usize>, b: Option<usize>) -> Option<usize> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, b) => a.or(b),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::{Block, Def, Delimiter, FieldName, Len, StrLen, ToValuesError, U64};
+
+    use super::*;
+
+    #[test]
+    fn test_max_len() {
+        let mut block = Block::new();
+        block.add_field(Name::BytesVarLen, Def::Bytes(Len::Var));
+        block
+            .add_field(Name::Str, Def::Str(StrLen::Nul))
+            .set_max_len(2);
+        block.add_field(Name::BytesRemaining, Def::Bytes(Len::Remaining));
+
+        let mut values = HashMap::new();
+
+        // the length prefix claims far more than the buffer holds
+        let buf = vec![0x80 | 0x3f, 0xff, 0xff, 0xff, 1, 2];
+        let e = block.to_values(&buf, &mut values).unwrap_err();
+        assert_eq!(e, ToValuesError::NotEnoughData(Name::BytesVarLen));
+
+        block.set_limits(Limits {
+            max_len: Some(3),
+            ..Default::default()
+        });
+        let e = block.to_values(&buf, &mut values).unwrap_err();
+        assert_eq!(e, ToValuesError::LimitExceeded(Name::BytesVarLen));
+        let e = block
+            .to_selected_values(&buf, &[Name::Str], &mut values)
+            .unwrap_err();
+        assert_eq!(e, ToValuesError::LimitExceeded(Name::BytesVarLen));
+
+        let buf = vec![3, 1, 2, 3, b'a', b'b', b'c'];
+        let e = block.to_values(&buf, &mut values).unwrap_err();
+        assert_eq!(e, ToValuesError::LimitExceeded(Name::Str));
+
+        let buf = vec![3, 1, 2, 3, b'a', b'b', b'c', 0];
+        let e = block.to_values(&buf, &mut values).unwrap_err();
+        assert_eq!(e, ToValuesError::LimitExceeded(Name::Str));
+
+        let buf = vec![3, 1, 2, 3, b'a', b'b', 0, 1, 2, 3, 4];
+        let e = block.to_values(&buf, &mut values).unwrap_err();
+        assert_eq!(e, ToValuesError::LimitExceeded(Name::BytesRemaining));
+
+        let buf = vec![3, 1, 2, 3, b'a', b'b', 0, 1, 2, 3];
+        block.to_values(&buf, &mut values).unwrap();
+    }
+
+    #[test]
+    fn test_delimited() {
+        let mut block = Block::new();
+        block.add_field(
+            Name::BytesDelimited,
+            Def::Bytes(Len::Delimited(Delimiter::new(b"\r\n".to_vec()))),
+        );
+        block.set_limits(Limits {
+            max_len: Some(2),
+            ..Default::default()
+        });
+
+        let mut values = HashMap::new();
+
+        let e = block.to_values(b"abc", &mut values).unwrap_err();
+        assert_eq!(e, ToValuesError::LimitExceeded(Name::BytesDelimited));
+        let e = block
+            .view(b"abc\r\n")
+            .get(&Name::BytesDelimited)
+            .unwrap_err();
+        assert_eq!(e, ToValuesError::LimitExceeded(Name::BytesDelimited));
+        block.to_values(b"ab\r\n", &mut values).unwrap();
+    }
+
+    #[test]
+    fn test_max_size() {
+        let mut block = Block::new();
+        block.add_field(Name::VarInt, Def::VarInt(U64::Var));
+        block.add_field(Name::BytesVarLen, Def::Bytes(Len::Var));
+        block.set_limits(Limits {
+            max_size: Some(4),
+            ..Default::default()
+        });
+
+        let mut values = HashMap::new();
+
+        let buf = vec![1, 3, 1, 2, 3];
+        let e = block.to_values(&buf, &mut values).unwrap_err();
+        assert_eq!(e, ToValuesError::LimitExceeded(Name::BytesVarLen));
+        let e = block.compile().to_values(&buf, &mut values).unwrap_err();
+        assert_eq!(e, ToValuesError::LimitExceeded(Name::BytesVarLen));
+
+        let buf = vec![1, 2, 1, 2, 0xff];
+        let end = block.to_values(&buf, &mut values).unwrap();
+        assert_eq!(end, 4);
+    }
+
+    #[derive(Debug, PartialEq, Eq, Hash, Clone)]
+    enum Name {
+        VarInt,
+        BytesVarLen,
+        BytesDelimited,
+        BytesRemaining,
+        Str,
+    }
+
+    impl FieldName for Name {}
+}
@@ -0,0 +1,337 @@
+/// The byte order of a fixed-width number.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Endian {
+    Big,
+    Little,
+}
+
+impl Endian {
+    /// Reads an unsigned integer of `x.len()` bytes, at most 8.
+    #[must_use]
+    pub fn read(&self, x: &[u8]) -> u64 {
+        match self {
+            Endian::Big => x.iter().fold(0, |acc, x| acc << 8 | *x as u64),
+            Endian::Little => x.iter().rev().fold(0, |acc, x| acc << 8 | *x as u64),
+        }
+    }
+
+    /// Writes the low `y.len()` bytes of `x` into `y`, at most 8.
+    pub fn write(&self, x: u64, y: &mut [u8]) {
+        let bytes = x.to_le_bytes();
+        let bytes = &bytes[..y.len()];
+        match self {
+            Endian::Big => y
+                .iter_mut()
+                .zip(bytes.iter().rev())
+                .for_each(|(y, x)| *y = *x),
+            Endian::Little => y.copy_from_slice(bytes),
+        }
+    }
+}
+
+/// A binary fixed-point number, as in the Q format.
+///
+/// The value is the integer in the field divided by `2^frac_bits`. The sign
+/// bit, if any, counts toward `int_bits`, and `int_bits + frac_bits` must be
+/// 8, 16, 32 or 64. Decodes to [`Val::F64`](crate::Val::F64).
+#[derive(Debug, Clone, Copy)]
+pub struct FixedPoint {
+    pub int_bits: u32,
+    pub frac_bits: u32,
+    pub signed: bool,
+    pub endian: Endian,
+}
+
+impl FixedPoint {
+    /// Returns the encoded size in bytes.
+    #[must_use]
+    pub fn size(&self) -> usize {
+        (self.bits() / 8) as usize
+    }
+
+    fn bits(&self) -> u32 {
+        self.int_bits + self.frac_bits
+    }
+
+    pub(crate) fn is_valid(&self) -> bool {
+        matches!(self.bits(), 8 | 16 | 32 | 64)
+    }
+
+    #[must_use]
+    pub fn decode(&self, raw: u64) -> f64 {
+        let shift = 64 - self.bits();
+        let x = match self.signed {
+            true => ((raw << shift) as i64 >> shift) as f64,
+            false => raw as f64,
+        };
+        x / 2f64.powi(self.frac_bits as i32)
+    }
+
+    /// Returns the raw integer nearest to `x`, or `None` if it does not fit.
+    #[must_use]
+    pub fn encode(&self, x: f64) -> Option<u64> {
+        let y = (x * 2f64.powi(self.frac_bits as i32)).round();
+        // bounds are powers of two and exact as floats
+        let (min, end) = match self.signed {
+            true => (
+                -(2f64.powi(self.bits() as i32 - 1)),
+                2f64.powi(self.bits() as i32 - 1),
+            ),
+            false => (0., 2f64.powi(self.bits() as i32)),
+        };
+        if !(min <= y && y < end) {
+            return None;
+        }
+        let raw = match self.signed {
+            true => y as i64 as u64,
+            false => y as u64,
+        };
+        let mask = u64::MAX >> (64 - self.bits());
+        Some(raw & mask)
+    }
+}
+
+/// A linear map from the raw integer of a field to a physical value: `raw * scale + offset`.
+///
+/// Attached with [`Field::set_scale`](crate::Field::set_scale).
+#[derive(Debug, Clone, Copy)]
+pub struct Scale {
+    pub scale: f64,
+    pub offset: f64,
+    /// How a physical value between two raw values is encoded
+    pub rounding: Rounding,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rounding {
+    Nearest,
+    Down,
+    Up,
+    /// Rejects values that do not map to a raw value within a relative error of `1e-9`
+    Exact,
+}
+
+impl Scale {
+    /// Returns a scale that rounds to the nearest raw value.
+    #[must_use]
+    pub fn new(scale: f64, offset: f64) -> Self {
+        Scale {
+            scale,
+            offset,
+            rounding: Rounding::Nearest,
+        }
+    }
+
+    #[must_use]
+    pub fn to_physical(&self, raw: u64) -> f64 {
+        raw as f64 * self.scale + self.offset
+    }
+
+    /// Returns the raw value of `x`, or `None` if it has none that is a valid varint.
+    #[must_use]
+    pub fn to_raw(&self, x: f64) -> Option<u64> {
+        let y = (x - self.offset) / self.scale;
+        let y = match self.rounding {
+            Rounding::Nearest => y.round(),
+            Rounding::Down => y.floor(),
+            Rounding::Up => y.ceil(),
+            Rounding::Exact => {
+                let z = y.round();
+                if (y - z).abs() > 1e-9 * z.abs().max(1.) {
+                    return None;
+                }
+                z
+            }
+        };
+        // varints hold up to 62 bits
+        if !(0. <= y && y < 2f64.powi(62)) {
+            return None;
+        }
+        Some(y as u64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::{Block, Def, FieldName, ToBytesError, Val, U64};
+
+    use super::*;
+
+    #[test]
+    fn test_endian() {
+        assert_eq!(Endian::Big.read(&[1, 2, 3]), 0x010203);
+        assert_eq!(Endian::Little.read(&[1, 2, 3]), 0x030201);
+
+        let mut buf = [0; 3];
+        Endian::Big.write(0x010203, &mut buf);
+        assert_eq!(buf, [1, 2, 3]);
+        Endian::Little.write(0x010203, &mut buf);
+        assert_eq!(buf, [3, 2, 1]);
+    }
+
+    #[test]
+    fn test_fixed_point() {
+        // Q1.15
+        let q15 = FixedPoint {
+            int_bits: 1,
+            frac_bits: 15,
+            signed: true,
+            endian: Endian::Big,
+        };
+        assert_eq!(q15.size(), 2);
+        assert_eq!(q15.decode(0x4000), 0.5);
+        assert_eq!(q15.decode(0x8000), -1.0);
+        assert_eq!(q15.encode(-0.5), Some(0xc000));
+        assert_eq!(q15.encode(-1.0), Some(0x8000));
+        assert_eq!(q15.encode(1.0), None);
+        assert_eq!(q15.encode(f64::NAN), None);
+
+        // UQ8.8
+        let uq8 = FixedPoint {
+            int_bits: 8,
+            frac_bits: 8,
+            signed: false,
+            endian: Endian::Little,
+        };
+        assert_eq!(uq8.decode(0x0180), 1.5);
+        assert_eq!(uq8.encode(255.99), Some(0xfffd));
+        assert_eq!(uq8.encode(-0.1), None);
+        // rounds to nearest
+        assert_eq!(uq8.encode(1.0 / 512.0 + 1e-9), Some(1));
+
+        let q63 = FixedPoint {
+            int_bits: 1,
+            frac_bits: 63,
+            signed: true,
+            endian: Endian::Big,
+        };
+        assert_eq!(q63.decode(u64::MAX), -(2f64.powi(-63)));
+        assert_eq!(q63.encode(-1.0), Some(1 << 63));
+    }
+
+    #[test]
+    fn test_block() {
+        let mut block = Block::new();
+        block.add_field(Name::F32, Def::F32(Endian::Little));
+        block.add_field(Name::F64, Def::F64(Endian::Big));
+        block.add_field(
+            Name::Fixed,
+            Def::FixedPoint(FixedPoint {
+                int_bits: 8,
+                frac_bits: 8,
+                signed: true,
+                endian: Endian::Big,
+            }),
+        );
+
+        let mut values = HashMap::new();
+        values.insert(Name::F32, Val::F32(1.5));
+        values.insert(Name::F64, Val::F64(-2.25));
+        values.insert(Name::Fixed, Val::F64(-1.5));
+
+        let mut buf = vec![0; 1024];
+        let end = block.to_bytes(&values, &mut buf).unwrap();
+        buf.truncate(end);
+        let mut expected = 1.5f32.to_le_bytes().to_vec();
+        expected.extend((-2.25f64).to_be_bytes());
+        expected.extend([0xfe, 0x80]);
+        assert_eq!(buf, expected);
+
+        let mut decoded = HashMap::new();
+        block.to_values(&buf, &mut decoded).unwrap();
+        assert_eq!(decoded[&Name::F32].value.f32().unwrap(), 1.5);
+        assert_eq!(decoded[&Name::F64].value.f64().unwrap(), -2.25);
+        assert_eq!(decoded[&Name::Fixed].value.f64().unwrap(), -1.5);
+        let mut decoded = HashMap::new();
+        block.compile().to_values(&buf, &mut decoded).unwrap();
+        assert_eq!(decoded[&Name::F32].value, Val::F32(1.5));
+
+        values.insert(Name::Fixed, Val::F64(128.0));
+        let e = block.to_bytes(&values, &mut buf).unwrap_err();
+        assert_eq!(e, ToBytesError::InvalidValue(Name::Fixed));
+        values.insert(Name::Fixed, Val::F32(1.0));
+        values.insert(Name::F32, Val::F64(1.5));
+        let e = block.to_bytes(&values, &mut buf).unwrap_err();
+        assert_eq!(e, ToBytesError::InvalidValue(Name::F32));
+    }
+
+    #[test]
+    fn test_scale() {
+        let scale = Scale::new(0.5, -40.);
+        assert_eq!(scale.to_physical(0), -40.);
+        assert_eq!(scale.to_physical(100), 10.);
+        assert_eq!(scale.to_raw(10.), Some(100));
+        assert_eq!(scale.to_raw(10.2), Some(100));
+        assert_eq!(scale.to_raw(-40.5), None);
+        assert_eq!(scale.to_raw(f64::NAN), None);
+        assert_eq!(scale.to_raw(1e30), None);
+
+        let scale = Scale {
+            rounding: Rounding::Down,
+            ..Scale::new(0.1, 0.)
+        };
+        assert_eq!(scale.to_raw(0.39), Some(3));
+        let scale = Scale {
+            rounding: Rounding::Up,
+            ..scale
+        };
+        assert_eq!(scale.to_raw(0.31), Some(4));
+        let scale = Scale {
+            rounding: Rounding::Exact,
+            ..scale
+        };
+        assert_eq!(scale.to_raw(0.3), Some(3));
+        assert_eq!(scale.to_raw(0.31), None);
+    }
+
+    #[test]
+    fn test_scale_block() {
+        let mut block = Block::new();
+        block
+            .add_field(Name::Scaled, Def::VarInt(U64::Var))
+            .set_scale(Scale::new(0.5, -40.));
+
+        let mut values = HashMap::new();
+        values.insert(Name::Scaled, Val::F64(10.));
+        let mut buf = [0; 8];
+        let end = block.to_bytes(&values, &mut buf).unwrap();
+        assert_eq!(&buf[..end], &[0x40, 100]);
+        values.insert(Name::Scaled, Val::VarInt(100));
+        let end = block.to_bytes(&values, &mut buf).unwrap();
+        assert_eq!(&buf[..end], &[0x40, 100]);
+
+        let mut decoded = HashMap::new();
+        block.to_values(&buf[..end], &mut decoded).unwrap();
+        let info = &decoded[&Name::Scaled];
+        assert_eq!(info.value, Val::VarInt(100));
+        assert_eq!(info.physical, Some(10.));
+
+        values.insert(Name::Scaled, Val::F32(-41.));
+        let e = block.to_bytes(&values, &mut buf).unwrap_err();
+        assert_eq!(e, ToBytesError::OutOfRange(Name::Scaled));
+        // fits the scale but not the field
+        values.insert(Name::Scaled, Val::F64(3e18));
+        let e = block.to_bytes(&values, &mut buf).unwrap_err();
+        assert_eq!(e, ToBytesError::OutOfRange(Name::Scaled));
+
+        let mut block = Block::new();
+        block
+            .add_field(Name::Scaled, Def::Bcd(crate::Bcd::Packed { len: 1 }))
+            .set_scale(Scale::new(1.0, 0.0));
+        values.insert(Name::Scaled, Val::F64(1000.));
+        let e = block.to_bytes(&values, &mut buf).unwrap_err();
+        assert_eq!(e, ToBytesError::OutOfRange(Name::Scaled));
+    }
+
+    #[derive(Debug, PartialEq, Eq, Hash, Clone)]
+    enum Name {
+        Scaled,
+        F32,
+        F64,
+        Fixed,
+    }
+
+    impl FieldName for Name {}
+}
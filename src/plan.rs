@@ -0,0 +1,318 @@
+use octets::{Octets, OctetsMut};
+
+use crate::{
+    block::fixed_size_at, Block, Def, Deferred, FieldName, Len, Span, ToValuesError, Val, ValInfo,
+    ValueSink, U64,
+};
+
+/// A decode plan compiled from a [`Block`].
+///
+/// The offsets of the fields in the fixed-size prefix of the block are
+/// computed once at compile time. Decoding checks the length of the buffer
+/// against the prefix once and then slices each prefix field out directly.
+/// Fields after the prefix are decoded sequentially as in
+/// [`Block::to_values`]. Buffers with a constant varint in the prefix encoded
+/// in more bytes than the minimum are decoded sequentially as a whole.
+pub struct DecodePlan<'block, F>
+where
+    F: FieldName,
+{
+    block: &'block Block<F>,
+    /// One slot per field in the fixed-size prefix
+    slots: Vec<Slot<'block>>,
+    prefix_len: usize,
+}
+
+struct Slot<'block> {
+    pos: usize,
+    size: usize,
+    kind: SlotKind<'block>,
+}
+
+enum SlotKind<'block> {
+    /// The encoding is known in advance and decodes to the given value
+    Const(Vec<u8>, u64),
+    /// The encoding is known in advance and decodes to itself
+    ConstBytes(&'block [u8]),
+    Bytes,
+    /// Decoded by the field itself from its slice
+    Field,
+}
+
+impl<'block, F> DecodePlan<'block, F>
+where
+    F: FieldName,
+{
+    #[must_use]
+    pub fn new(block: &'block Block<F>) -> Self {
+        let mut slots = Vec::new();
+        let mut pos = 0;
+        for field in block.fields() {
+            let size = match fixed_size_at(field.def(), pos) {
+                Some(size) => size,
+                None => break,
+            };
+            let kind = match field.def() {
+                _ if field.checks_value() => SlotKind::Field,
+                Def::VarInt(U64::Fixed(x)) => {
+                    let mut buf = vec![0; size];
+                    let mut b = OctetsMut::with_slice(&mut buf);
+                    b.put_varint(*x).unwrap();
+                    SlotKind::Const(buf, *x)
+                }
+                Def::FixedBytes(x) => SlotKind::ConstBytes(x),
+                Def::Bytes(Len::Fixed(_)) => SlotKind::Bytes,
+                _ => SlotKind::Field,
+            };
+            slots.push(Slot { pos, size, kind });
+            pos += size;
+        }
+        DecodePlan {
+            block,
+            slots,
+            prefix_len: pos,
+        }
+    }
+
+    /// Returns the length of the fixed-size prefix of the block.
+    #[must_use]
+    pub fn prefix_len(&self) -> usize {
+        self.prefix_len
+    }
+
+    /// Returns the offset of the field named `name` if it is in the fixed-size prefix.
+    #[must_use]
+    pub fn fixed_offset(&self, name: &F) -> Option<usize> {
+        let index = self.block.position(name)?;
+        self.slots.get(index).map(|slot| slot.pos)
+    }
+
+    pub fn to_values<'buf, V>(
+        &self,
+        b: &'buf [u8],
+        values: &mut V,
+    ) -> Result<usize, ToValuesError<F>>
+    where
+        V: ValueSink<'buf, F>,
+    {
+        self.block.check_trailer()?;
+        let fields = self.block.fields();
+        values.prepare(fields.len());
+
+        if b.len() < self.prefix_len {
+            // Reports the same error as decoding sequentially
+            return self.block.to_values(b, values);
+        }
+
+        if !self.block.has_checksums() {
+            return match self.decode_fields(b, values, None)? {
+                Some(end) => Ok(end),
+                None => self.block.to_values(b, values),
+            };
+        }
+        // Values are only passed on once the checksums are verified
+        let mut deferred = Deferred::default();
+        let mut spans = Vec::new();
+        let end = match self.decode_fields(b, &mut deferred, Some(&mut spans))? {
+            Some(end) => end,
+            None => return self.block.to_values(b, values),
+        };
+        self.block.check_checksums(&b[..end], &spans)?;
+        deferred.flush(fields, values);
+        Ok(end)
+    }
+
+    /// Decodes the fields, recording their spans in `spans` if given.
+    ///
+    /// Returns `None` if the offsets of the prefix do not hold for `b`.
+    fn decode_fields<'buf, V>(
+        &self,
+        b: &'buf [u8],
+        values: &mut V,
+        mut spans: Option<&mut Vec<Span>>,
+    ) -> Result<Option<usize>, ToValuesError<F>>
+    where
+        V: ValueSink<'buf, F>,
+    {
+        let fields = self.block.fields();
+        if let Some(spans) = spans.as_deref_mut() {
+            spans.extend(self.slots.iter().map(|slot| Span {
+                pos: slot.pos,
+                len: slot.size,
+            }));
+        }
+
+        for (i, (slot, field)) in self.slots.iter().zip(fields).enumerate() {
+            self.block.check_size(field, slot.pos + slot.size)?;
+            let x = &b[slot.pos..slot.pos + slot.size];
+            let value = match &slot.kind {
+                SlotKind::Const(y, value) => {
+                    if !field.def().has_fixed_size_in(x) {
+                        return Ok(None);
+                    }
+                    if x != y {
+                        return Err(ToValuesError::InvalidValue(field.name().clone()));
+                    }
+                    Val::VarInt(*value)
+                }
+                SlotKind::ConstBytes(y) => {
+                    if x != *y {
+                        return Err(ToValuesError::InvalidValue(field.name().clone()));
+                    }
+                    Val::Bytes(x)
+                }
+                SlotKind::Bytes => Val::Bytes(x),
+                SlotKind::Field => {
+                    let mut b = Octets::with_slice(&b[..slot.pos + slot.size]);
+                    b.skip(slot.pos).unwrap();
+                    let value = field.to_value_(&mut b, self.block.limits())?;
+                    if field.def().has_value() {
+                        values.insert(i, field.name(), value);
+                    }
+                    continue;
+                }
+            };
+            let pos = slot.pos;
+            let span = Span {
+                pos,
+                len: slot.size,
+            };
+            let prefix = None;
+            values.insert(
+                i,
+                field.name(),
+                ValInfo {
+                    value,
+                    pos,
+                    span,
+                    prefix,
+                    physical: None,
+                },
+            );
+        }
+
+        let mut b = Octets::with_slice(b);
+        b.skip(self.prefix_len).unwrap();
+        for (i, field) in fields.iter().enumerate().skip(self.slots.len()) {
+            let value = field.to_value_(&mut b, self.block.limits())?;
+            self.block.check_size(field, b.off())?;
+            if let Some(spans) = spans.as_deref_mut() {
+                spans.push(value.span);
+            }
+            if field.def().has_value() {
+                values.insert(i, field.name(), value);
+            }
+        }
+        Ok(Some(b.off()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    #[test]
+    fn test_to_values() {
+        let block = get_block();
+        let plan = block.compile();
+        assert_eq!(plan.prefix_len(), 7);
+        assert_eq!(plan.fixed_offset(&Name::BytesFixedLen), Some(2));
+        assert_eq!(plan.fixed_offset(&Name::FixedBytes), Some(4));
+        assert_eq!(plan.fixed_offset(&Name::BytesVarLen), None);
+
+        let buf = vec![0x41, 0x02, 7, 8, 0xba, 0xad, 0x0d, 2, 1, 2];
+        let mut values = HashMap::new();
+        let end = plan.to_values(&buf, &mut values).unwrap();
+        assert_eq!(end, buf.len());
+
+        let mut expected = HashMap::new();
+        block.to_values(&buf, &mut expected).unwrap();
+        assert_eq!(values, expected);
+    }
+
+    #[test]
+    fn test_non_minimal_constant() {
+        let block = get_block();
+        let plan = block.compile();
+        let buf = vec![0x80, 0, 0x01, 0x02, 7, 8, 0xba, 0xad, 0x0d, 2, 1, 2];
+
+        let mut values = HashMap::new();
+        let end = plan.to_values(&buf, &mut values).unwrap();
+        assert_eq!(end, buf.len());
+
+        let mut expected = HashMap::new();
+        block.to_values(&buf, &mut expected).unwrap();
+        assert_eq!(values, expected);
+        assert_eq!(values[&Name::BytesFixedLen].pos, 4);
+    }
+
+    #[test]
+    fn test_align() {
+        let mut block = Block::new();
+        block.add_field(Name::FixedVarInt, Def::VarInt(U64::Fixed(5)));
+        block.add_field(Name::Align, Def::Align { to: 4, fill: 0 });
+        block.add_field(Name::BytesFixedLen, Def::Bytes(Len::Fixed(2)));
+        block.add_field(Name::BytesVarLen, Def::Bytes(Len::Var));
+        let plan = block.compile();
+        assert_eq!(plan.prefix_len(), 6);
+        assert_eq!(plan.fixed_offset(&Name::BytesFixedLen), Some(4));
+
+        for buf in [
+            vec![5, 0, 0, 0, 7, 8, 1, 9],
+            vec![0x40, 5, 0, 0, 7, 8, 1, 9],
+        ] {
+            let mut values = HashMap::new();
+            let end = plan.to_values(&buf, &mut values).unwrap();
+            assert_eq!(end, buf.len());
+
+            let mut expected = HashMap::new();
+            block.to_values(&buf, &mut expected).unwrap();
+            assert_eq!(values, expected);
+        }
+    }
+
+    #[test]
+    fn test_errors() {
+        let block = get_block();
+        let plan = block.compile();
+        let mut values = HashMap::new();
+
+        let buf = vec![0x41, 0x02, 7, 8, 0xba];
+        let e = plan.to_values(&buf, &mut values).unwrap_err();
+        assert_eq!(e, ToValuesError::NotEnoughData(Name::FixedBytes));
+
+        let buf = vec![0x41, 0x03, 7, 8, 0xba, 0xad, 0x0d, 0];
+        let e = plan.to_values(&buf, &mut values).unwrap_err();
+        assert_eq!(e, ToValuesError::InvalidValue(Name::FixedVarInt));
+
+        let buf = vec![0x41, 0x02, 7, 8, 0xba, 0xad, 0x0e, 0];
+        let e = plan.to_values(&buf, &mut values).unwrap_err();
+        assert_eq!(e, ToValuesError::InvalidValue(Name::FixedBytes));
+
+        let buf = vec![0x41, 0x02, 7, 8, 0xba, 0xad, 0x0d, 2, 1];
+        let e = plan.to_values(&buf, &mut values).unwrap_err();
+        assert_eq!(e, ToValuesError::NotEnoughData(Name::BytesVarLen));
+    }
+
+    fn get_block() -> Block<Name> {
+        let mut block = Block::new();
+        block.add_field(Name::FixedVarInt, Def::VarInt(U64::Fixed(0x0102)));
+        block.add_field(Name::BytesFixedLen, Def::Bytes(Len::Fixed(2)));
+        block.add_field(Name::FixedBytes, Def::FixedBytes(vec![0xba, 0xad, 0x0d]));
+        block.add_field(Name::BytesVarLen, Def::Bytes(Len::Var));
+        block
+    }
+
+    #[derive(Debug, PartialEq, Eq, Hash, Clone)]
+    enum Name {
+        FixedVarInt,
+        BytesFixedLen,
+        FixedBytes,
+        BytesVarLen,
+        Align,
+    }
+
+    impl FieldName for Name {}
+}
@@ -0,0 +1,1050 @@
+//! Serde support for values, behind the `serde` feature.
+//!
+//! Values take their natural form: integers and floats are numbers, labels,
+//! strings, addresses, MACs and UUIDs are strings, bytes are strings in a
+//! [`BytesFormat`] and times are objects with whole `secs` since 1970, negative
+//! before it, and `nanos` after them, so no precision is lost. TLV
+//! items are objects with `type` and `value`, and the fields of a nested block
+//! are arrays. Integers of a field with a [`Scale`] are physical values in
+//! [`Block::serialize_values`] and [`Block::deserialize_values`]; nested fields
+//! keep raw values both ways, as decoding them yields no physical value.
+
+use std::{
+    collections::HashMap,
+    fmt,
+    hash::Hash,
+    marker::PhantomData,
+    net::{Ipv4Addr, Ipv6Addr},
+    time::{Duration, UNIX_EPOCH},
+};
+
+use serde::{
+    de::{self, DeserializeSeed, IgnoredAny, MapAccess, SeqAccess, Unexpected, Visitor},
+    ser::{self, SerializeMap, SerializeSeq, SerializeStruct},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+
+use crate::{Block, Def, Field, FieldName, Labels, OwnedVal, Scale, Tlv, Val, ValInfo};
+
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// How bytes are written as strings.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BytesFormat {
+    /// Lower-case hex digits; either case is read
+    #[default]
+    Hex,
+    /// Standard base64 with padding as in RFC 4648; padding is optional when read
+    Base64,
+}
+
+impl BytesFormat {
+    #[must_use]
+    pub fn encode(&self, x: &[u8]) -> String {
+        match self {
+            BytesFormat::Hex => x.iter().map(|x| format!("{x:02x}")).collect(),
+            BytesFormat::Base64 => {
+                let mut y = String::new();
+                for chunk in x.chunks(3) {
+                    let mut group = [0; 3];
+                    group[..chunk.len()].copy_from_slice(chunk);
+                    let n = u32::from_be_bytes([0, group[0], group[1], group[2]]);
+                    for i in 0..4 {
+                        match i <= chunk.len() {
+                            true => y.push(BASE64[(n >> (18 - 6 * i) & 0x3f) as usize] as char),
+                            false => y.push('='),
+                        }
+                    }
+                }
+                y
+            }
+        }
+    }
+
+    /// Returns the bytes in `x`, or `None` if it is not in this format.
+    #[must_use]
+    pub fn decode(&self, x: &str) -> Option<Vec<u8>> {
+        match self {
+            BytesFormat::Hex => {
+                if !x.len().is_multiple_of(2) || !x.bytes().all(|c| c.is_ascii_hexdigit()) {
+                    return None;
+                }
+                (0..x.len())
+                    .step_by(2)
+                    .map(|i| u8::from_str_radix(x.get(i..i + 2)?, 16).ok())
+                    .collect()
+            }
+            BytesFormat::Base64 => {
+                let mut y = Vec::new();
+                let mut n: u32 = 0;
+                let mut bits = 0;
+                for c in x.trim_end_matches('=').bytes() {
+                    let digit = BASE64.iter().position(|x| *x == c)? as u32;
+                    n = n << 6 | digit;
+                    bits += 6;
+                    if bits >= 8 {
+                        bits -= 8;
+                        y.push((n >> bits) as u8);
+                        n &= (1 << bits) - 1;
+                    }
+                }
+                // a single digit left over cannot make a byte
+                if bits >= 6 {
+                    return None;
+                }
+                Some(y)
+            }
+        }
+    }
+}
+
+/// A [`Val`] serialized with bytes in a given format.
+struct ValSer<'a, 'buf> {
+    value: &'a Val<'buf>,
+    bytes: BytesFormat,
+}
+
+impl Serialize for ValSer<'_, '_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let bytes = self.bytes;
+        match self.value {
+            Val::VarInt(x) => serializer.serialize_u64(*x),
+            Val::Enum(_, label) => serializer.serialize_str(label),
+            Val::Bytes(x) => serializer.serialize_str(&bytes.encode(x)),
+            Val::Str(x) => serializer.serialize_str(x),
+            Val::F32(x) => serializer.serialize_f32(*x),
+            Val::F64(x) => serializer.serialize_f64(*x),
+            Val::Bool(x) => serializer.serialize_bool(*x),
+            Val::Ipv4(_) | Val::Ipv6(_) | Val::Mac(_) | Val::Uuid(_) => {
+                serializer.collect_str(self.value)
+            }
+            Val::Time(x) => {
+                let (secs, nanos) = match x.duration_since(UNIX_EPOCH) {
+                    Ok(d) => (i64::try_from(d.as_secs()), d.subsec_nanos()),
+                    // round down to whole seconds so that `nanos` is added
+                    Err(e) => match e.duration() {
+                        d if d.subsec_nanos() == 0 => (i64::try_from(d.as_secs()).map(|x| -x), 0),
+                        d => (
+                            i64::try_from(d.as_secs()).map(|x| -x - 1),
+                            1_000_000_000 - d.subsec_nanos(),
+                        ),
+                    },
+                };
+                let secs = secs.map_err(|_| ser::Error::custom("time is out of range"))?;
+                let mut time = serializer.serialize_struct("Time", 2)?;
+                time.serialize_field("secs", &secs)?;
+                time.serialize_field("nanos", &nanos)?;
+                time.end()
+            }
+            Val::Tlv(items) => {
+                let mut seq = serializer.serialize_seq(Some(items.len()))?;
+                for item in items {
+                    seq.serialize_element(&TlvItemSer {
+                        ty: item.ty,
+                        value: ValSer {
+                            value: &item.value,
+                            bytes,
+                        },
+                    })?;
+                }
+                seq.end()
+            }
+            Val::Fields(values) => {
+                let mut seq = serializer.serialize_seq(Some(values.len()))?;
+                for value in values {
+                    seq.serialize_element(&ValSer { value, bytes })?;
+                }
+                seq.end()
+            }
+        }
+    }
+}
+
+struct TlvItemSer<'a, 'buf> {
+    ty: u64,
+    value: ValSer<'a, 'buf>,
+}
+
+impl Serialize for TlvItemSer<'_, '_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut item = serializer.serialize_struct("TlvItem", 2)?;
+        item.serialize_field("type", &self.ty)?;
+        item.serialize_field("value", &self.value)?;
+        item.end()
+    }
+}
+
+/// Bytes are written in [`BytesFormat::Hex`].
+///
+/// There is no `Deserialize`: bytes read as hex or base64 and strings with
+/// escapes are decoded into new buffers, which a borrowed `Val` cannot hold.
+/// Deserialize an [`OwnedVal`] and borrow from it with [`OwnedVal::as_val`].
+impl Serialize for Val<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        ValSer {
+            value: self,
+            bytes: BytesFormat::Hex,
+        }
+        .serialize(serializer)
+    }
+}
+
+/// There is no `Deserialize`: positions and spans describe a decoded buffer
+/// and cannot be encoded from; deserialize the values alone as [`OwnedVal`]
+/// instead.
+impl Serialize for ValInfo<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut info = serializer.serialize_struct("ValInfo", 5)?;
+        info.serialize_field("value", &self.value)?;
+        info.serialize_field("pos", &self.pos)?;
+        info.serialize_field("span", &self.span)?;
+        info.serialize_field("prefix", &self.prefix)?;
+        info.serialize_field("physical", &self.physical)?;
+        info.end()
+    }
+}
+
+impl Serialize for OwnedVal {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        self.as_val().serialize(serializer)
+    }
+}
+
+/// Without a block to tell the field types, unsigned integers read as
+/// [`OwnedVal::VarInt`], other numbers as [`OwnedVal::F64`], strings as
+/// [`OwnedVal::Str`] and arrays as [`OwnedVal::Fields`]. Use
+/// [`Block::deserialize_values`] to read values by their field definitions.
+impl<'de> Deserialize<'de> for OwnedVal {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(UntypedVisitor)
+    }
+}
+
+struct UntypedVisitor;
+
+impl<'de> Visitor<'de> for UntypedVisitor {
+    type Value = OwnedVal;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a number, string, boolean or array")
+    }
+
+    fn visit_u64<E>(self, v: u64) -> Result<Self::Value, E> {
+        Ok(OwnedVal::VarInt(v))
+    }
+
+    fn visit_i64<E>(self, v: i64) -> Result<Self::Value, E> {
+        Ok(OwnedVal::F64(v as f64))
+    }
+
+    fn visit_f64<E>(self, v: f64) -> Result<Self::Value, E> {
+        Ok(OwnedVal::F64(v))
+    }
+
+    fn visit_bool<E>(self, v: bool) -> Result<Self::Value, E> {
+        Ok(OwnedVal::Bool(v))
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E> {
+        Ok(OwnedVal::Str(v.to_string()))
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut values = Vec::new();
+        while let Some(value) = seq.next_element()? {
+            values.push(value);
+        }
+        Ok(OwnedVal::Fields(values))
+    }
+}
+
+/// Decoded values serialized as a map in field order.
+///
+/// Returned by [`Block::serialize_values`].
+pub struct SerializeValues<'a, 'buf, F>
+where
+    F: FieldName,
+{
+    block: &'a Block<F>,
+    values: &'a HashMap<F, ValInfo<'buf>>,
+    bytes: BytesFormat,
+}
+
+impl<F> Serialize for SerializeValues<'_, '_, F>
+where
+    F: FieldName + Hash + Serialize,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut map = serializer.serialize_map(None)?;
+        for field in self.block.fields() {
+            match self.values.get(field.name()) {
+                // labels win over physical values
+                Some(ValInfo {
+                    value: Val::VarInt(_),
+                    physical: Some(x),
+                    ..
+                }) => map.serialize_entry(field.name(), x)?,
+                Some(info) => {
+                    let value = ValSer {
+                        value: &info.value,
+                        bytes: self.bytes,
+                    };
+                    map.serialize_entry(field.name(), &value)?;
+                }
+                None => (),
+            }
+        }
+        map.end()
+    }
+}
+
+impl<F> Block<F>
+where
+    F: FieldName,
+{
+    /// Returns the decoded `values` for serializing as a map in field order.
+    ///
+    /// Only the values are written, with the physical value for a field with
+    /// a [`Scale`]; serialize a [`ValInfo`] itself for its position.
+    #[must_use]
+    pub fn serialize_values<'a, 'buf>(
+        &'a self,
+        values: &'a HashMap<F, ValInfo<'buf>>,
+        bytes: BytesFormat,
+    ) -> SerializeValues<'a, 'buf, F> {
+        SerializeValues {
+            block: self,
+            values,
+            bytes,
+        }
+    }
+
+    /// Reads a map of values for [`Block::to_bytes`], typed by the field definitions.
+    ///
+    /// Labels are read as the integers they stand for, and numbers of a field
+    /// with a [`Scale`] as physical values. TLV items must give their `type`
+    /// before their `value`. Values of fields without one, such as padding,
+    /// are ignored. Custom fields cannot be read and fail with an error.
+    pub fn deserialize_values<'de, D>(
+        &self,
+        deserializer: D,
+        bytes: BytesFormat,
+    ) -> Result<HashMap<F, OwnedVal>, D::Error>
+    where
+        D: Deserializer<'de>,
+        F: Deserialize<'de> + Hash,
+    {
+        deserializer.deserialize_map(ValuesVisitor {
+            block: self,
+            bytes,
+            _name: PhantomData,
+        })
+    }
+}
+
+struct ValuesVisitor<'a, 'de, F>
+where
+    F: FieldName,
+{
+    block: &'a Block<F>,
+    bytes: BytesFormat,
+    _name: PhantomData<&'de ()>,
+}
+
+impl<'de, F> Visitor<'de> for ValuesVisitor<'_, 'de, F>
+where
+    F: FieldName + Deserialize<'de> + Hash,
+{
+    type Value = HashMap<F, OwnedVal>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a map of field names to values")
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut values = HashMap::new();
+        while let Some(name) = map.next_key::<F>()? {
+            let field = match self.block.field(&name) {
+                Some(field) => field,
+                None => return Err(de::Error::custom("no such field in the block")),
+            };
+            if !field.def().has_value() {
+                map.next_value::<IgnoredAny>()?;
+                continue;
+            }
+            let value = map.next_value_seed(FieldSeed::new(field, self.bytes))?;
+            values.insert(name, value);
+        }
+        Ok(values)
+    }
+}
+
+/// Reads the value of a field by its definition.
+#[derive(Clone, Copy)]
+pub(crate) struct FieldSeed<'a> {
+    def: &'a Def,
+    labels: Option<&'a Labels>,
+    /// `None` for nested fields, which take raw values
+    scale: Option<&'a Scale>,
+    bytes: BytesFormat,
+}
+
+impl<'a> FieldSeed<'a> {
+    fn new<F>(field: &'a Field<F>, bytes: BytesFormat) -> Self
+    where
+        F: FieldName,
+    {
+        FieldSeed {
+            scale: field.scale(),
+            ..FieldSeed::nested(field, bytes)
+        }
+    }
+
+    /// Reads the value of a field in a TLV item or nested block.
+    pub(crate) fn nested<F>(field: &'a Field<F>, bytes: BytesFormat) -> Self
+    where
+        F: FieldName,
+    {
+        FieldSeed {
+            def: field.def(),
+            labels: field.labels(),
+            scale: None,
+            bytes,
+        }
+    }
+}
+
+impl<'de> DeserializeSeed<'de> for FieldSeed<'_> {
+    type Value = OwnedVal;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        match self.def {
+            Def::Tlv(tlv) => deserializer.deserialize_seq(TlvSeed {
+                tlv,
+                bytes: self.bytes,
+            }),
+            Def::Custom(_) => Err(de::Error::custom(
+                "custom fields cannot be read from a serialized value",
+            )),
+            _ => deserializer.deserialize_any(self),
+        }
+    }
+}
+
+impl<'de> Visitor<'de> for FieldSeed<'_> {
+    type Value = OwnedVal;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let def = self.def;
+        let x = match def {
+            _ if def.is_int() => match (self.labels, self.scale) {
+                (Some(_), Some(_)) => "a label or physical value",
+                (Some(_), None) => "an unsigned integer or label",
+                (None, Some(_)) => "a physical value",
+                (None, None) => "an unsigned integer",
+            },
+            Def::Checksum(_) => "an unsigned integer",
+            Def::Bytes(_) | Def::FixedBytes(_) => return BytesVisitor(self.bytes).expecting(f),
+            Def::Str(_) => "a string",
+            Def::F32(_) | Def::F64(_) | Def::FixedPoint(_) => "a number",
+            Def::Bool => "a boolean",
+            Def::Ipv4 => "an IPv4 address",
+            Def::Ipv6 => "an IPv6 address",
+            Def::Mac => "a MAC address",
+            Def::Uuid => "a UUID",
+            Def::Timestamp(_) => "an object with seconds and nanoseconds since 1970",
+            _ => "a field that can be read from a serialized value",
+        };
+        f.write_str(x)
+    }
+
+    fn visit_u64<E>(self, v: u64) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        match self.def {
+            def if def.is_int() && self.scale.is_some() => Ok(OwnedVal::F64(v as f64)),
+            def if def.is_int() => Ok(OwnedVal::VarInt(v)),
+            Def::Checksum(_) => Ok(OwnedVal::VarInt(v)),
+            Def::F32(_) | Def::F64(_) | Def::FixedPoint(_) | Def::Timestamp(_) => {
+                self.visit_f64(v as f64)
+            }
+            _ => Err(E::invalid_type(Unexpected::Unsigned(v), &self)),
+        }
+    }
+
+    fn visit_i64<E>(self, v: i64) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        match self.def {
+            _ if v >= 0 => self.visit_u64(v as u64),
+            Def::F32(_) | Def::F64(_) | Def::FixedPoint(_) | Def::Timestamp(_) => {
+                self.visit_f64(v as f64)
+            }
+            def if def.is_int() && self.scale.is_some() => self.visit_f64(v as f64),
+            _ => Err(E::invalid_type(Unexpected::Signed(v), &self)),
+        }
+    }
+
+    fn visit_f64<E>(self, v: f64) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        match self.def {
+            Def::F32(_) => Ok(OwnedVal::F32(v as f32)),
+            Def::F64(_) | Def::FixedPoint(_) => Ok(OwnedVal::F64(v)),
+            def if def.is_int() && self.scale.is_some() => Ok(OwnedVal::F64(v)),
+            Def::Timestamp(_) => {
+                let time = match Duration::try_from_secs_f64(v.abs()) {
+                    Ok(d) if v < 0.0 => UNIX_EPOCH.checked_sub(d),
+                    Ok(d) => UNIX_EPOCH.checked_add(d),
+                    Err(_) => None,
+                };
+                match time {
+                    Some(x) => Ok(OwnedVal::Time(x)),
+                    None => Err(E::invalid_value(Unexpected::Float(v), &self)),
+                }
+            }
+            _ => Err(E::invalid_type(Unexpected::Float(v), &self)),
+        }
+    }
+
+    fn visit_bool<E>(self, v: bool) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        match self.def {
+            Def::Bool => Ok(OwnedVal::Bool(v)),
+            _ => Err(E::invalid_type(Unexpected::Bool(v), &self)),
+        }
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        let invalid = || E::invalid_value(Unexpected::Str(v), &self);
+        let y = match self.def {
+            def if def.is_int() => match self.labels.map(|x| x.value(v)) {
+                Some(Some(x)) => OwnedVal::VarInt(x),
+                Some(None) => return Err(invalid()),
+                None => return Err(E::invalid_type(Unexpected::Str(v), &self)),
+            },
+            Def::Bytes(_) | Def::FixedBytes(_) => match self.bytes.decode(v) {
+                Some(x) => OwnedVal::Bytes(x),
+                None => return Err(invalid()),
+            },
+            Def::Str(_) => OwnedVal::Str(v.to_string()),
+            Def::Ipv4 => match v.parse::<Ipv4Addr>() {
+                Ok(x) => OwnedVal::Ipv4(x),
+                Err(_) => return Err(invalid()),
+            },
+            Def::Ipv6 => match v.parse::<Ipv6Addr>() {
+                Ok(x) => OwnedVal::Ipv6(x),
+                Err(_) => return Err(invalid()),
+            },
+            Def::Mac => match BytesFormat::Hex.decode(&v.replace([':', '-'], "")) {
+                Some(x) if x.len() == 6 => OwnedVal::Mac(x.try_into().unwrap()),
+                _ => return Err(invalid()),
+            },
+            Def::Uuid => match BytesFormat::Hex.decode(&v.replace('-', "")) {
+                Some(x) if x.len() == 16 => OwnedVal::Uuid(x.try_into().unwrap()),
+                _ => return Err(invalid()),
+            },
+            _ => return Err(E::invalid_type(Unexpected::Str(v), &self)),
+        };
+        Ok(y)
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        if !matches!(self.def, Def::Timestamp(_)) {
+            return Err(de::Error::invalid_type(Unexpected::Map, &self));
+        }
+        let mut secs = None;
+        let mut nanos = None;
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "secs" if secs.is_none() => secs = Some(map.next_value::<i64>()?),
+                "nanos" if nanos.is_none() => nanos = Some(map.next_value::<u32>()?),
+                "secs" | "nanos" => return Err(de::Error::custom(format!("duplicate `{key}`"))),
+                _ => return Err(de::Error::unknown_field(&key, &["secs", "nanos"])),
+            }
+        }
+        let secs = secs.ok_or_else(|| de::Error::missing_field("secs"))?;
+        let nanos = nanos.ok_or_else(|| de::Error::missing_field("nanos"))?;
+        if nanos >= 1_000_000_000 {
+            return Err(de::Error::invalid_value(
+                Unexpected::Unsigned(nanos.into()),
+                &"fewer than 10^9 nanoseconds",
+            ));
+        }
+        let time = match u64::try_from(secs) {
+            Ok(x) => UNIX_EPOCH.checked_add(Duration::new(x, nanos)),
+            Err(_) => UNIX_EPOCH
+                .checked_sub(Duration::from_secs(secs.unsigned_abs()))
+                .and_then(|x| x.checked_add(Duration::from_nanos(nanos.into()))),
+        };
+        match time {
+            Some(x) => Ok(OwnedVal::Time(x)),
+            None => Err(de::Error::custom("time is out of range")),
+        }
+    }
+}
+
+/// Reads the items of a [`Def::Tlv`] field.
+struct TlvSeed<'a> {
+    tlv: &'a Tlv,
+    bytes: BytesFormat,
+}
+
+impl<'de> Visitor<'de> for TlvSeed<'_> {
+    type Value = OwnedVal;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("an array of TLV items")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut items = Vec::new();
+        while let Some(item) = seq.next_element_seed(TlvItemSeed {
+            tlv: self.tlv,
+            bytes: self.bytes,
+        })? {
+            items.push(item);
+        }
+        Ok(OwnedVal::Tlv(items))
+    }
+}
+
+struct TlvItemSeed<'a> {
+    tlv: &'a Tlv,
+    bytes: BytesFormat,
+}
+
+impl<'de> DeserializeSeed<'de> for TlvItemSeed<'_> {
+    type Value = (u64, OwnedVal);
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_struct("TlvItem", &["type", "value"], self)
+    }
+}
+
+impl<'de> Visitor<'de> for TlvItemSeed<'_> {
+    type Value = (u64, OwnedVal);
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a TLV item with `type` and `value`")
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut ty = None;
+        let mut value = None;
+        while let Some(key) = map.next_key::<String>()? {
+            match (key.as_str(), ty) {
+                ("type", _) => ty = Some(map.next_value()?),
+                ("value", Some(ty)) => {
+                    value = Some(map.next_value_seed(self.tlv.item_seed(ty, self.bytes))?)
+                }
+                ("value", None) => {
+                    return Err(de::Error::custom("`type` must come before `value`"))
+                }
+                (key, _) => return Err(de::Error::unknown_field(key, &["type", "value"])),
+            }
+        }
+        match (ty, value) {
+            (Some(ty), Some(value)) => Ok((ty, value)),
+            (None, _) => Err(de::Error::missing_field("type")),
+            (_, None) => Err(de::Error::missing_field("value")),
+        }
+    }
+}
+
+/// Reads the value of a TLV item by the entry for its type.
+pub(crate) enum ItemSeed<'a> {
+    Field(FieldSeed<'a>),
+    /// The fields of a nested block that take a value
+    Block(Vec<FieldSeed<'a>>),
+    Bytes(BytesFormat),
+}
+
+impl<'de> DeserializeSeed<'de> for ItemSeed<'_> {
+    type Value = OwnedVal;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        match self {
+            ItemSeed::Field(x) => x.deserialize(deserializer),
+            ItemSeed::Block(x) => deserializer.deserialize_seq(FieldsVisitor(x)),
+            ItemSeed::Bytes(bytes) => deserializer.deserialize_str(BytesVisitor(bytes)),
+        }
+    }
+}
+
+struct FieldsVisitor<'a>(Vec<FieldSeed<'a>>);
+
+impl<'de> Visitor<'de> for FieldsVisitor<'_> {
+    type Value = OwnedVal;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "an array of at most {} values", self.0.len())
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut values = Vec::new();
+        for field in self.0.iter().copied() {
+            match seq.next_element_seed(field)? {
+                Some(value) => values.push(value),
+                None => return Ok(OwnedVal::Fields(values)),
+            }
+        }
+        // missing trailing values are not provided, extra ones are an error
+        if seq.next_element::<IgnoredAny>()?.is_some() {
+            return Err(de::Error::invalid_length(values.len() + 1, &self));
+        }
+        Ok(OwnedVal::Fields(values))
+    }
+}
+
+/// Reads the bytes of a TLV item of an unknown type.
+struct BytesVisitor(BytesFormat);
+
+impl Visitor<'_> for BytesVisitor {
+    type Value = OwnedVal;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.0 {
+            BytesFormat::Hex => f.write_str("a hex string"),
+            BytesFormat::Base64 => f.write_str("a base64 string"),
+        }
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        match self.0.decode(v) {
+            Some(x) => Ok(OwnedVal::Bytes(x)),
+            None => Err(E::invalid_value(Unexpected::Str(v), &self)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Labels, Len, Scale, StrLen, TlvInt, U64};
+
+    use super::*;
+
+    #[test]
+    fn test_bytes_format() {
+        for (x, hex, base64) in [
+            (&b""[..], "", ""),
+            (b"f", "66", "Zg=="),
+            (b"fo", "666f", "Zm8="),
+            (b"foo", "666f6f", "Zm9v"),
+            (b"foob", "666f6f62", "Zm9vYg=="),
+        ] {
+            assert_eq!(BytesFormat::Hex.encode(x), hex);
+            assert_eq!(BytesFormat::Hex.decode(hex).unwrap(), x);
+            assert_eq!(BytesFormat::Base64.encode(x), base64);
+            assert_eq!(BytesFormat::Base64.decode(base64).unwrap(), x);
+        }
+        assert_eq!(BytesFormat::Hex.decode("0A").unwrap(), [10]);
+        assert_eq!(BytesFormat::Hex.decode("0"), None);
+        assert_eq!(BytesFormat::Hex.decode("0g"), None);
+        assert_eq!(BytesFormat::Hex.decode("+f"), None);
+        assert_eq!(BytesFormat::Base64.decode("Zm9vYg").unwrap(), b"foob");
+        assert_eq!(BytesFormat::Base64.decode("Z"), None);
+        assert_eq!(BytesFormat::Base64.decode("Zm9v!"), None);
+    }
+
+    #[test]
+    fn test_val() {
+        let json = serde_json::to_string(&Val::Bytes(&[0xde, 0xad])).unwrap();
+        assert_eq!(json, r#""dead""#);
+        let json = serde_json::to_string(&Val::Enum(1, "On".into())).unwrap();
+        assert_eq!(json, r#""On""#);
+        let json = serde_json::to_string(&Val::Mac([0, 1, 2, 3, 4, 0xff])).unwrap();
+        assert_eq!(json, r#""00:01:02:03:04:ff""#);
+        let value = Val::Tlv(vec![crate::TlvItem {
+            ty: 1,
+            value: Val::Fields(vec![Val::VarInt(2), Val::Str("a")]),
+        }]);
+        let json = serde_json::to_string(&value).unwrap();
+        assert_eq!(json, r#"[{"type":1,"value":[2,"a"]}]"#);
+
+        let owned: OwnedVal = serde_json::from_str(r#"[1, -1, "a", true]"#).unwrap();
+        assert_eq!(
+            owned,
+            OwnedVal::Fields(vec![
+                OwnedVal::VarInt(1),
+                OwnedVal::F64(-1.0),
+                OwnedVal::Str("a".to_string()),
+                OwnedVal::Bool(true),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_block() {
+        let block = get_block();
+        let mut values = HashMap::new();
+        values.insert(Name::State, Val::VarInt(1));
+        values.insert(Name::Temp, Val::F64(21.5));
+        values.insert(Name::Label, Val::Str("a\"b"));
+        values.insert(Name::Data, Val::Bytes(&[0xff, 0]));
+        let mut buf = vec![0; 1024];
+        let end = block.to_bytes(&values, &mut buf).unwrap();
+        buf.truncate(end);
+
+        let mut decoded = HashMap::new();
+        block.to_values(&buf, &mut decoded).unwrap();
+        let json =
+            serde_json::to_string(&block.serialize_values(&decoded, BytesFormat::Base64)).unwrap();
+        assert_eq!(
+            json,
+            r#"{"Magic":7,"State":"On","Temp":21.5,"Label":"a\"b","Data":"/wA="}"#
+        );
+        let info = serde_json::to_string(&decoded[&Name::Temp]).unwrap();
+        assert_eq!(
+            info,
+            r#"{"value":43,"pos":2,"span":{"pos":2,"len":1},"prefix":null,"physical":21.5}"#
+        );
+
+        // the other way round
+        let again = encode(&block, &json, BytesFormat::Base64);
+        assert_eq!(again, buf);
+        let again = encode(
+            &block,
+            r#"{"State": 1, "Temp": 21.5, "Label": "a\"b", "Data": "/wA=", "Padding": null}"#,
+            BytesFormat::Base64,
+        );
+        assert_eq!(again, buf);
+
+        // integers of a scaled field are physical values too
+        let mut de = serde_json::Deserializer::from_str(r#"{"Temp": 43}"#);
+        let owned = block.deserialize_values(&mut de, BytesFormat::Hex).unwrap();
+        assert_eq!(owned[&Name::Temp], OwnedVal::F64(43.0));
+    }
+
+    #[test]
+    fn test_time() {
+        let mut block = Block::new();
+        block.add_field(Name::Data, Def::Timestamp(crate::Timestamp::Ntp));
+        let mut values = HashMap::new();
+        values.insert(Name::Data, Val::Time(UNIX_EPOCH - Duration::from_secs(60)));
+        let mut buf = vec![0; 8];
+        block.to_bytes(&values, &mut buf).unwrap();
+
+        let mut decoded = HashMap::new();
+        block.to_values(&buf, &mut decoded).unwrap();
+        let json =
+            serde_json::to_string(&block.serialize_values(&decoded, BytesFormat::Hex)).unwrap();
+        assert_eq!(json, r#"{"Data":{"secs":-60,"nanos":0}}"#);
+        assert_eq!(encode(&block, &json, BytesFormat::Hex), buf);
+        assert_eq!(encode(&block, r#"{"Data": -60}"#, BytesFormat::Hex), buf);
+
+        let mut block = Block::new();
+        block.add_field(
+            Name::Data,
+            Def::Timestamp(crate::Timestamp::Unix {
+                unit: crate::TimeUnit::Nanos,
+                size: 8,
+                endian: crate::Endian::Big,
+            }),
+        );
+        let time = UNIX_EPOCH + Duration::new(1_700_000_000, 123_456_789);
+        values.insert(Name::Data, Val::Time(time));
+        let mut buf = vec![0; 8];
+        block.to_bytes(&values, &mut buf).unwrap();
+        let mut decoded = HashMap::new();
+        block.to_values(&buf, &mut decoded).unwrap();
+        let json =
+            serde_json::to_string(&block.serialize_values(&decoded, BytesFormat::Hex)).unwrap();
+        assert_eq!(json, r#"{"Data":{"secs":1700000000,"nanos":123456789}}"#);
+        assert_eq!(encode(&block, &json, BytesFormat::Hex), buf);
+
+        let time = UNIX_EPOCH - Duration::from_millis(1500);
+        let json = serde_json::to_string(&Val::Time(time)).unwrap();
+        assert_eq!(json, r#"{"secs":-2,"nanos":500000000}"#);
+        let mut de = serde_json::Deserializer::from_str(r#"{"Data": {"secs": 1}}"#);
+        assert!(block.deserialize_values(&mut de, BytesFormat::Hex).is_err());
+        let mut de =
+            serde_json::Deserializer::from_str(r#"{"Data": {"secs": 1, "nanos": 1000000000}}"#);
+        assert!(block.deserialize_values(&mut de, BytesFormat::Hex).is_err());
+    }
+
+    #[test]
+    fn test_nested() {
+        let mut inner = Block::new();
+        inner
+            .add_field(Name::State, Def::VarInt(U64::Var))
+            .set_labels(Labels::new(vec![(0, "Off"), (1, "On")]));
+        inner.add_field(Name::Padding, Def::Padding { len: 1, fill: 0 });
+        inner.add_field(Name::Label, Def::Str(StrLen::Var));
+        let mut tlv = Tlv::new(TlvInt::Varint, TlvInt::Varint);
+        tlv.add_def(1, Def::Ipv4).add_block(2, inner);
+        let mut block = Block::new();
+        block.add_field(Name::Data, Def::Tlv(tlv));
+
+        let json = r#"{"Data":[{"type":1,"value":"10.0.0.1"},{"type":2,"value":["On","a"]},{"type":9,"value":"ff00"}]}"#;
+        let buf = encode(&block, json, BytesFormat::Hex);
+        let mut decoded = HashMap::new();
+        block.to_values(&buf, &mut decoded).unwrap();
+        let again =
+            serde_json::to_string(&block.serialize_values(&decoded, BytesFormat::Hex)).unwrap();
+        assert_eq!(again, json);
+
+        let read = |json: &str| {
+            let mut de = serde_json::Deserializer::from_str(json);
+            block
+                .deserialize_values(&mut de, BytesFormat::Hex)
+                .unwrap_err()
+                .to_string()
+        };
+        assert_eq!(
+            read(r#"{"Data": [{"value": "ff", "type": 9}]}"#),
+            "`type` must come before `value` at line 1 column 18"
+        );
+        assert_eq!(
+            read(r#"{"Data": [{"type": 2, "value": ["On", "a", 1]}]}"#),
+            "invalid length 3, expected an array of at most 2 values at line 1 column 45"
+        );
+    }
+
+    #[test]
+    fn test_custom() {
+        struct Raw;
+
+        impl crate::Codec for Raw {
+            fn encode(&self, _value: &Val, _b: &mut [u8]) -> Result<usize, crate::CodecError> {
+                Ok(0)
+            }
+
+            fn decode<'buf>(
+                &self,
+                _b: &'buf [u8],
+            ) -> Result<(Val<'buf>, usize), crate::CodecError> {
+                Ok((Val::Bool(true), 0))
+            }
+        }
+
+        let mut block = Block::new();
+        block.add_field(Name::Data, Def::Custom(Box::new(Raw)));
+        let mut de = serde_json::Deserializer::from_str(r#"{"Data": true}"#);
+        let e = block.deserialize_values(&mut de, BytesFormat::Hex);
+        assert!(e
+            .unwrap_err()
+            .to_string()
+            .starts_with("custom fields cannot be read from a serialized value"));
+    }
+
+    #[test]
+    fn test_invalid() {
+        let block = get_block();
+        let read = |json: &str| {
+            let mut de = serde_json::Deserializer::from_str(json);
+            block
+                .deserialize_values(&mut de, BytesFormat::Hex)
+                .unwrap_err()
+                .to_string()
+        };
+        assert_eq!(
+            read(r#"{"State": "Dim"}"#),
+            r#"invalid value: string "Dim", expected an unsigned integer or label at line 1 column 15"#
+        );
+        assert_eq!(
+            read(r#"{"Data": 1}"#),
+            "invalid type: integer `1`, expected a hex string at line 1 column 10"
+        );
+        assert!(read(r#"{"Other": 1}"#).starts_with("unknown variant `Other`"));
+    }
+
+    fn encode(block: &Block<Name>, json: &str, bytes: BytesFormat) -> Vec<u8> {
+        let mut de = serde_json::Deserializer::from_str(json);
+        let owned = block.deserialize_values(&mut de, bytes).unwrap();
+        let values = owned
+            .iter()
+            .map(|(name, value)| (name.clone(), value.as_val()))
+            .collect();
+        let mut buf = vec![0; 1024];
+        let end = block.to_bytes(&values, &mut buf).unwrap();
+        buf.truncate(end);
+        buf
+    }
+
+    fn get_block() -> Block<Name> {
+        let mut block = Block::new();
+        block.add_field(Name::Magic, Def::VarInt(U64::Fixed(7)));
+        block
+            .add_field(Name::State, Def::VarInt(U64::Var))
+            .set_labels(Labels::new(vec![(0, "Off"), (1, "On")]));
+        block
+            .add_field(Name::Temp, Def::VarInt(U64::Var))
+            .set_scale(Scale::new(0.5, 0.0));
+        block.add_field(Name::Label, Def::Str(StrLen::Var));
+        block.add_field(Name::Data, Def::Bytes(Len::Var));
+        block.add_field(Name::Padding, Def::Padding { len: 1, fill: 0 });
+        block
+    }
+
+    #[derive(Debug, PartialEq, Eq, Hash, Clone, Serialize, Deserialize)]
+    enum Name {
+        Magic,
+        State,
+        Temp,
+        Label,
+        Data,
+        Padding,
+    }
+
+    impl FieldName for Name {}
+}
@@ -0,0 +1,168 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    hash::Hash,
+};
+
+use crate::{Field, FieldName, ValInfo};
+
+/// Storage that decoded values are written into.
+pub trait ValueSink<'buf, F>
+where
+    F: FieldName,
+{
+    /// Stores the value of the field named `name` at position `index` in the block.
+    fn insert(&mut self, index: usize, name: &F, value: ValInfo<'buf>);
+
+    /// Prepares for the values of a block with `len` fields before decoding starts.
+    fn prepare(&mut self, len: usize) {
+        let _ = len;
+    }
+}
+
+impl<'buf, F> ValueSink<'buf, F> for HashMap<F, ValInfo<'buf>>
+where
+    F: FieldName + Hash,
+{
+    fn insert(&mut self, _index: usize, name: &F, value: ValInfo<'buf>) {
+        HashMap::insert(self, name.clone(), value);
+    }
+}
+
+impl<'buf, F> ValueSink<'buf, F> for BTreeMap<F, ValInfo<'buf>>
+where
+    F: FieldName + Ord,
+{
+    fn insert(&mut self, _index: usize, name: &F, value: ValInfo<'buf>) {
+        BTreeMap::insert(self, name.clone(), value);
+    }
+}
+
+/// Values are indexed by field position.
+///
+/// Every slot is cleared before decoding, so an array can be reused across
+/// buffers without keeping values from the previous one.
+///
+/// # Panics
+///
+/// Panics before decoding if the block has more than `N` fields.
+impl<'buf, F, const N: usize> ValueSink<'buf, F> for [Option<ValInfo<'buf>>; N]
+where
+    F: FieldName,
+{
+    fn insert(&mut self, index: usize, _name: &F, value: ValInfo<'buf>) {
+        self[index] = Some(value);
+    }
+
+    fn prepare(&mut self, len: usize) {
+        assert!(
+            len <= N,
+            "an array of {N} values cannot hold the values of a block with {len} fields"
+        );
+        self.iter_mut().for_each(|x| *x = None);
+    }
+}
+
+/// Holds values back until the block is known to be valid as a whole.
+#[derive(Default)]
+pub(crate) struct Deferred<'buf>(Vec<(usize, ValInfo<'buf>)>);
+
+impl<'buf> Deferred<'buf> {
+    /// Passes the values held back on to `values`.
+    pub(crate) fn flush<F, V>(self, fields: &[Field<F>], values: &mut V)
+    where
+        F: FieldName,
+        V: ValueSink<'buf, F>,
+    {
+        for (index, value) in self.0 {
+            values.insert(index, fields[index].name(), value);
+        }
+    }
+}
+
+impl<'buf, F> ValueSink<'buf, F> for Deferred<'buf>
+where
+    F: FieldName,
+{
+    fn insert(&mut self, index: usize, _name: &F, value: ValInfo<'buf>) {
+        self.0.push((index, value));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Block, Def, Len, U64};
+
+    use super::*;
+
+    #[test]
+    fn test_btree_map() {
+        let block = get_block();
+        let buf = vec![0x12 | 0x40, 0x34, 2, 1, 2];
+
+        let mut values = BTreeMap::new();
+        let end = block.to_values(&buf, &mut values).unwrap();
+        assert_eq!(end, buf.len());
+
+        let mut names = values.keys();
+        assert_eq!(names.next(), Some(&Name::VarInt));
+        assert_eq!(names.next(), Some(&Name::BytesVarLen));
+        assert_eq!(names.next(), None);
+    }
+
+    #[test]
+    fn test_array() {
+        let block = get_block();
+        let buf = vec![0x12 | 0x40, 0x34, 2, 1, 2];
+
+        let mut values = [None, None];
+        let end = block.to_values(&buf, &mut values).unwrap();
+        assert_eq!(end, buf.len());
+
+        let ValInfo { value, pos, .. } = values[0].as_ref().unwrap();
+        assert_eq!(value.varint().unwrap(), 0x1234);
+        assert_eq!(*pos, 0);
+
+        let ValInfo { value, pos, .. } = values[1].as_ref().unwrap();
+        assert_eq!(value.bytes().unwrap(), vec![1, 2]);
+        assert_eq!(*pos, 2);
+    }
+
+    #[test]
+    fn test_array_reuse() {
+        let block = get_block();
+        let buf = vec![0x12 | 0x40, 0x34, 2, 1, 2];
+
+        let mut values = [None, None];
+        block.to_values(&buf, &mut values).unwrap();
+        block
+            .to_selected_values(&buf, &[Name::VarInt], &mut values)
+            .unwrap();
+        assert!(values[0].is_some());
+        assert!(values[1].is_none());
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "an array of 1 values cannot hold the values of a block with 2 fields"
+    )]
+    fn test_array_too_short() {
+        let block = get_block();
+        let mut values = [None];
+        let _ = block.to_values(&[], &mut values);
+    }
+
+    fn get_block() -> Block<Name> {
+        let mut block = Block::new();
+        block.add_field(Name::VarInt, Def::VarInt(U64::Var));
+        block.add_field(Name::BytesVarLen, Def::Bytes(Len::Var));
+        block
+    }
+
+    #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone)]
+    enum Name {
+        VarInt,
+        BytesVarLen,
+    }
+
+    impl FieldName for Name {}
+}
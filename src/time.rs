@@ -0,0 +1,162 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::Endian;
+
+/// Seconds from the NTP epoch of 1900 to the UNIX epoch of 1970
+const NTP_UNIX_OFFSET: u64 = 2_208_988_800;
+
+/// A point in time that decodes to [`Val::Time`](crate::Val::Time).
+#[derive(Debug, Clone, Copy)]
+pub enum Timestamp {
+    /// Time since 1970 in `size` bytes, 4 or 8; encoding truncates to the unit
+    Unix {
+        unit: TimeUnit,
+        size: usize,
+        endian: Endian,
+    },
+    /// Seconds since 1900 and a 32-bit fraction in network byte order, as in RFC 5905
+    ///
+    /// Only the era ending in 2036 is supported.
+    Ntp,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeUnit {
+    Secs,
+    Millis,
+    Micros,
+    Nanos,
+}
+
+impl Timestamp {
+    /// Returns the encoded size in bytes.
+    #[must_use]
+    pub fn size(&self) -> usize {
+        match self {
+            Timestamp::Unix { size, .. } => *size,
+            Timestamp::Ntp => 8,
+        }
+    }
+
+    #[must_use]
+    pub fn endian(&self) -> Endian {
+        match self {
+            Timestamp::Unix { endian, .. } => *endian,
+            Timestamp::Ntp => Endian::Big,
+        }
+    }
+
+    pub(crate) fn is_valid(&self) -> bool {
+        matches!(self.size(), 4 | 8)
+    }
+
+    /// Returns the time of the raw integer, or `None` if the platform cannot represent it.
+    #[must_use]
+    pub fn decode(&self, raw: u64) -> Option<SystemTime> {
+        match self {
+            Timestamp::Unix { unit, .. } => {
+                let since = match unit {
+                    TimeUnit::Secs => Duration::from_secs(raw),
+                    TimeUnit::Millis => Duration::from_millis(raw),
+                    TimeUnit::Micros => Duration::from_micros(raw),
+                    TimeUnit::Nanos => Duration::from_nanos(raw),
+                };
+                UNIX_EPOCH.checked_add(since)
+            }
+            Timestamp::Ntp => {
+                let secs = raw >> 32;
+                let nanos = ((raw & 0xffff_ffff) * 1_000_000_000) >> 32;
+                let since = Duration::new(secs, nanos as u32);
+                ntp_epoch()?.checked_add(since)
+            }
+        }
+    }
+
+    /// Returns the raw integer of `time`, or `None` if the field cannot hold it.
+    #[must_use]
+    pub fn encode(&self, time: SystemTime) -> Option<u64> {
+        match self {
+            Timestamp::Unix { unit, size, .. } => {
+                let since = time.duration_since(UNIX_EPOCH).ok()?;
+                let raw = match unit {
+                    TimeUnit::Secs => since.as_secs() as u128,
+                    TimeUnit::Millis => since.as_millis(),
+                    TimeUnit::Micros => since.as_micros(),
+                    TimeUnit::Nanos => since.as_nanos(),
+                };
+                if raw >> (size * 8) != 0 {
+                    return None;
+                }
+                Some(raw as u64)
+            }
+            Timestamp::Ntp => {
+                let since = time.duration_since(ntp_epoch()?).ok()?;
+                if since.as_secs() >> 32 != 0 {
+                    return None;
+                }
+                // rounds up so that decoding gives back the same nanoseconds
+                let frac = ((since.subsec_nanos() as u64) << 32).div_ceil(1_000_000_000);
+                Some(since.as_secs() << 32 | frac)
+            }
+        }
+    }
+}
+
+fn ntp_epoch() -> Option<SystemTime> {
+    UNIX_EPOCH.checked_sub(Duration::from_secs(NTP_UNIX_OFFSET))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unix() {
+        let timestamp = Timestamp::Unix {
+            unit: TimeUnit::Millis,
+            size: 8,
+            endian: Endian::Big,
+        };
+        let time = UNIX_EPOCH + Duration::new(1_700_000_000, 123_456_789);
+        let raw = timestamp.encode(time).unwrap();
+        assert_eq!(raw, 1_700_000_000_123);
+        assert_eq!(
+            timestamp.decode(raw).unwrap(),
+            UNIX_EPOCH + Duration::from_millis(1_700_000_000_123)
+        );
+
+        let timestamp = Timestamp::Unix {
+            unit: TimeUnit::Secs,
+            size: 4,
+            endian: Endian::Big,
+        };
+        assert_eq!(timestamp.encode(time), Some(1_700_000_000));
+        let time = UNIX_EPOCH + Duration::from_secs(1 << 32);
+        assert_eq!(timestamp.encode(time), None);
+        let time = UNIX_EPOCH - Duration::from_secs(1);
+        assert_eq!(timestamp.encode(time), None);
+    }
+
+    #[test]
+    fn test_ntp() {
+        let timestamp = Timestamp::Ntp;
+        // 1970-01-01 in NTP
+        assert_eq!(timestamp.decode(NTP_UNIX_OFFSET << 32), Some(UNIX_EPOCH));
+        assert_eq!(timestamp.encode(UNIX_EPOCH), Some(NTP_UNIX_OFFSET << 32));
+
+        let time = UNIX_EPOCH + Duration::new(1_700_000_000, 500_000_000);
+        let raw = timestamp.encode(time).unwrap();
+        assert_eq!(raw & 0xffff_ffff, 0x8000_0000);
+        assert_eq!(timestamp.decode(raw), Some(time));
+
+        let time = UNIX_EPOCH + Duration::new(1_700_000_000, 999_999_999);
+        assert_eq!(
+            timestamp.decode(timestamp.encode(time).unwrap()),
+            Some(time)
+        );
+
+        // past the end of era 0 in 2036
+        let time = UNIX_EPOCH + Duration::from_secs(2_085_978_496);
+        assert_eq!(timestamp.encode(time), None);
+    }
+}
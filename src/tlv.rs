@@ -0,0 +1,753 @@
+use std::fmt::Debug;
+
+use octets::{Octets, OctetsMut};
+
+use crate::{
+    Block, Def, Field, FieldName, Limits, ToBytesError, ToValuesError, Val, ValInfo, ValueSink,
+};
+
+/// A sequence of type-length-value items that decodes to [`Val::Tlv`].
+///
+/// Items whose type has a definition decode through it, with the length as
+/// the extent of the value. Items of other types decode to [`Val::Bytes`] so
+/// they survive re-encoding. Items are encoded in the order given.
+pub struct Tlv {
+    pub ty: TlvInt,
+    pub len: TlvInt,
+    prefix: Option<TlvInt>,
+    entries: Vec<(u64, Entry)>,
+}
+
+/// The encoding of a type or length in a [`Tlv`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TlvInt {
+    /// A QUIC variable-length integer
+    Varint,
+    U8,
+    /// Two bytes in network byte order
+    U16,
+}
+
+/// A decoded item of a [`Tlv`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct TlvItem<'buf> {
+    pub ty: u64,
+    pub value: Val<'buf>,
+}
+
+/// What items of a type in a [`Tlv`] decode with, as returned by [`Tlv::entry`].
+pub enum TlvEntry<'a> {
+    Def(&'a Def),
+    /// The fields of a block in order, named by their `Debug` form
+    Block(Vec<(String, &'a Def)>),
+}
+
+enum Entry {
+    Def(Field<Anon>),
+    Block(Box<dyn NestedBlock>),
+}
+
+/// The name of the fields of a [`Tlv`], which are reported under the name of the TLV field.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub(crate) struct Anon;
+
+impl FieldName for Anon {}
+
+/// A block with its field names erased, encoding from and decoding to [`Val::Fields`].
+///
+/// Errors name the fields by their `Debug` form.
+trait NestedBlock: Send + Sync {
+    fn to_bytes(&self, values: &[Val], b: &mut [u8]) -> Result<usize, ToBytesError<String>>;
+
+    /// Decodes from the start of `b` under the tighter of `limits` and the limits of the block.
+    fn to_values<'buf>(
+        &self,
+        b: &'buf [u8],
+        limits: &Limits,
+    ) -> Result<(Vec<Val<'buf>>, usize), ToValuesError<String>>;
+
+    fn layout(&self) -> Vec<(String, &Def)>;
+
+    /// Returns how to read the values of the fields that take one.
+    #[cfg(feature = "serde")]
+    fn seeds(&self, bytes: crate::BytesFormat) -> Vec<crate::serialize::FieldSeed<'_>>;
+}
+
+impl<F> NestedBlock for Block<F>
+where
+    F: FieldName + Debug + Send + Sync,
+{
+    fn to_bytes(&self, values: &[Val], b: &mut [u8]) -> Result<usize, ToBytesError<String>> {
+        match self.to_bytes_positional(values, b) {
+            Ok(end) => Ok(end),
+            Err(e) => Err(e.map_name(|name| format!("{name:?}"))),
+        }
+    }
+
+    fn to_values<'buf>(
+        &self,
+        b: &'buf [u8],
+        limits: &Limits,
+    ) -> Result<(Vec<Val<'buf>>, usize), ToValuesError<String>> {
+        let limits = self.limits().tighter(limits);
+        let mut values = Positional(Vec::new());
+        match self.to_values_(&mut Octets::with_slice(b), &mut values, &limits) {
+            Ok(end) => Ok((values.0, end)),
+            Err(e) => Err(e.map_name(|name| format!("{name:?}"))),
+        }
+    }
+
+    fn layout(&self) -> Vec<(String, &Def)> {
+        self.fields()
+            .iter()
+            .map(|field| (format!("{:?}", field.name()), field.def()))
+            .collect()
+    }
+
+    #[cfg(feature = "serde")]
+    fn seeds(&self, bytes: crate::BytesFormat) -> Vec<crate::serialize::FieldSeed<'_>> {
+        self.fields()
+            .iter()
+            .filter(|field| field.def().has_value())
+            .map(|field| crate::serialize::FieldSeed::nested(field, bytes))
+            .collect()
+    }
+}
+
+/// Collects the values of a block in field order.
+struct Positional<'buf>(Vec<Val<'buf>>);
+
+impl<'buf, F> ValueSink<'buf, F> for Positional<'buf>
+where
+    F: FieldName,
+{
+    fn insert(&mut self, _index: usize, _name: &F, value: ValInfo<'buf>) {
+        self.0.push(value.value);
+    }
+}
+
+impl TlvInt {
+    pub(crate) fn get(&self, b: &mut Octets) -> Option<u64> {
+        let x = match self {
+            TlvInt::Varint => b.get_varint(),
+            TlvInt::U8 => b.get_u8().map(|x| x as u64),
+            TlvInt::U16 => b.get_u16().map(|x| x as u64),
+        };
+        x.ok()
+    }
+
+    fn fits(&self, x: u64) -> bool {
+        match self {
+            TlvInt::Varint => x < 1 << 62,
+            TlvInt::U8 => x <= u8::MAX as u64,
+            TlvInt::U16 => x <= u16::MAX as u64,
+        }
+    }
+
+    /// Returns the number of bytes `x` takes, which must fit.
+    fn size(&self, x: u64) -> usize {
+        match self {
+            TlvInt::Varint => octets::varint_len(x),
+            TlvInt::U8 => 1,
+            TlvInt::U16 => 2,
+        }
+    }
+
+    /// Writes what `put` writes to `b` prefixed by its length.
+    ///
+    /// The length is written in place once known, moving what `put` wrote
+    /// if the length takes more bytes than assumed.
+    fn put_prefixed(
+        &self,
+        b: &mut OctetsMut,
+        put: impl FnOnce(&mut OctetsMut) -> Result<(), ToBytesError<Anon>>,
+    ) -> Result<(), ToBytesError<Anon>> {
+        let assumed = self.size(0);
+        let rest = b.as_mut();
+        if rest.len() < assumed {
+            return Err(ToBytesError::NotEnoughSpace(Anon));
+        }
+        let mut o = OctetsMut::with_slice(&mut rest[assumed..]);
+        put(&mut o)?;
+        let len = o.off();
+        if !self.fits(len as u64) {
+            return Err(ToBytesError::OutOfRange(Anon));
+        }
+        let size = self.size(len as u64);
+        if size + len > rest.len() {
+            return Err(ToBytesError::NotEnoughSpace(Anon));
+        }
+        if size != assumed {
+            rest.copy_within(assumed..assumed + len, size);
+        }
+        assert!(self.put(len as u64, &mut OctetsMut::with_slice(&mut rest[..size])));
+        b.skip(size + len).unwrap();
+        Ok(())
+    }
+
+    /// Writes `x`, which must fit.
+    fn put(&self, x: u64, b: &mut OctetsMut) -> bool {
+        let res = match self {
+            TlvInt::Varint => b.put_varint(x).map(|_| ()),
+            TlvInt::U8 => b.put_u8(x as u8).map(|_| ()),
+            TlvInt::U16 => b.put_u16(x as u16).map(|_| ()),
+        };
+        res.is_ok()
+    }
+}
+
+impl Tlv {
+    /// Returns a sequence without known types that takes the rest of the buffer.
+    ///
+    /// Such a sequence can only be the last field of a block.
+    #[must_use]
+    pub fn new(ty: TlvInt, len: TlvInt) -> Self {
+        Tlv {
+            ty,
+            len,
+            prefix: None,
+            entries: Vec::new(),
+        }
+    }
+
+    /// Prefixes the items with their total length encoded as `prefix`, so other fields may follow.
+    pub fn set_prefix(&mut self, prefix: TlvInt) -> &mut Self {
+        self.prefix = Some(prefix);
+        self
+    }
+
+    /// Returns the encoding of the total length before the items, or `None` if they take the rest of the buffer.
+    #[must_use]
+    pub fn prefix(&self) -> Option<TlvInt> {
+        self.prefix
+    }
+
+    /// Decodes items of type `ty` with `def`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `ty` already has a definition or `def` is not valid.
+    pub fn add_def(&mut self, ty: u64, def: Def) -> &mut Self {
+        assert!(self.find(ty).is_none());
+        def.check_rep();
+        self.entries.push((ty, Entry::Def(Field::new(Anon, def))));
+        self
+    }
+
+    /// Decodes items of type `ty` with `block` into [`Val::Fields`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `ty` already has a definition.
+    pub fn add_block<F>(&mut self, ty: u64, block: Block<F>) -> &mut Self
+    where
+        F: FieldName + Debug + Send + Sync + 'static,
+    {
+        assert!(self.find(ty).is_none());
+        self.entries.push((ty, Entry::Block(Box::new(block))));
+        self
+    }
+
+    /// Returns whether items of type `ty` decode to something other than raw bytes.
+    #[must_use]
+    pub fn is_known(&self, ty: u64) -> bool {
+        self.find(ty).is_some()
+    }
+
+    /// Returns the types with a definition or block in the order they were added.
+    pub fn known_types(&self) -> impl Iterator<Item = u64> + '_ {
+        self.entries.iter().map(|(ty, _)| *ty)
+    }
+
+    /// Returns what items of type `ty` decode with, or `None` if they decode to raw bytes.
+    #[must_use]
+    pub fn entry(&self, ty: u64) -> Option<TlvEntry<'_>> {
+        match self.find(ty)? {
+            Entry::Def(field) => Some(TlvEntry::Def(field.def())),
+            Entry::Block(block) => Some(TlvEntry::Block(block.layout())),
+        }
+    }
+
+    /// Returns the definition of items of type `ty`, or `None` if they have none or decode with a block.
+    #[must_use]
+    pub fn def(&self, ty: u64) -> Option<&Def> {
+        match self.find(ty)? {
+            Entry::Def(field) => Some(field.def()),
+            Entry::Block(_) => None,
+        }
+    }
+
+    /// Returns how to read the value of an item of type `ty`.
+    #[cfg(feature = "serde")]
+    pub(crate) fn item_seed(
+        &self,
+        ty: u64,
+        bytes: crate::BytesFormat,
+    ) -> crate::serialize::ItemSeed<'_> {
+        use crate::serialize::{FieldSeed, ItemSeed};
+
+        match self.find(ty) {
+            Some(Entry::Def(field)) => ItemSeed::Field(FieldSeed::nested(field, bytes)),
+            Some(Entry::Block(block)) => ItemSeed::Block(block.seeds(bytes)),
+            None => ItemSeed::Bytes(bytes),
+        }
+    }
+
+    fn find(&self, ty: u64) -> Option<&Entry> {
+        self.entries
+            .iter()
+            .find(|(x, _)| *x == ty)
+            .map(|(_, entry)| entry)
+    }
+
+    pub(crate) fn to_bytes(
+        &self,
+        items: &[TlvItem],
+        b: &mut OctetsMut,
+    ) -> Result<(), ToBytesError<Anon>> {
+        match self.prefix {
+            Some(prefix) => prefix.put_prefixed(b, |b| self.put_items(items, b)),
+            None => self.put_items(items, b),
+        }
+    }
+
+    fn put_items(&self, items: &[TlvItem], b: &mut OctetsMut) -> Result<(), ToBytesError<Anon>> {
+        for item in items {
+            self.put_item(item, b)?;
+        }
+        Ok(())
+    }
+
+    fn put_item(&self, item: &TlvItem, b: &mut OctetsMut) -> Result<(), ToBytesError<Anon>> {
+        if !self.ty.fits(item.ty) {
+            return Err(ToBytesError::OutOfRange(Anon));
+        }
+        if !self.ty.put(item.ty, b) {
+            return Err(ToBytesError::NotEnoughSpace(Anon));
+        }
+        self.len
+            .put_prefixed(b, |b| match (self.find(item.ty), &item.value) {
+                (Some(Entry::Def(field)), value) => field.to_bytes(Some(value), b),
+                (Some(Entry::Block(block)), Val::Fields(values)) => {
+                    let len = match block.to_bytes(values, b.as_mut()) {
+                        Ok(len) => len,
+                        Err(e) => {
+                            return Err(ToBytesError::Nested {
+                                name: Anon,
+                                ty: item.ty,
+                                error: Box::new(e),
+                            })
+                        }
+                    };
+                    b.skip(len).unwrap();
+                    Ok(())
+                }
+                (None, Val::Bytes(x)) => match b.put_bytes(x) {
+                    Ok(_) => Ok(()),
+                    Err(_) => Err(ToBytesError::NotEnoughSpace(Anon)),
+                },
+                (_, _) => Err(ToBytesError::InvalidValue(Anon)),
+            })
+    }
+
+    /// Decodes all of `b` into items.
+    pub(crate) fn to_items<'buf>(
+        &self,
+        b: &'buf [u8],
+        limits: &Limits,
+    ) -> Result<Vec<TlvItem<'buf>>, ToValuesError<Anon>> {
+        let limits = match limits.max_depth {
+            Some(0) => return Err(ToValuesError::LimitExceeded(Anon)),
+            Some(depth) => Limits {
+                max_depth: Some(depth - 1),
+                ..limits.clone()
+            },
+            None => limits.clone(),
+        };
+        let mut o = Octets::with_slice(b);
+        let mut items = Vec::new();
+        while o.cap() > 0 {
+            if Some(items.len()) == limits.max_count {
+                return Err(ToValuesError::LimitExceeded(Anon));
+            }
+            let (ty, len) = match (self.ty.get(&mut o), self.len.get(&mut o)) {
+                (Some(ty), Some(len)) => (ty, len),
+                _ => return Err(ToValuesError::NotEnoughData(Anon)),
+            };
+            if let Some(max_len) = limits.max_len {
+                if len > max_len as u64 {
+                    return Err(ToValuesError::LimitExceeded(Anon));
+                }
+            }
+            if len > o.cap() as u64 {
+                return Err(ToValuesError::NotEnoughData(Anon));
+            }
+            let x = o.get_bytes(len as usize).unwrap().buf();
+            let value = match self.find(ty) {
+                Some(Entry::Def(field)) => {
+                    let mut v = Octets::with_slice(x);
+                    let info = field.to_value_(&mut v, &limits)?;
+                    // the value must fill its length
+                    if v.cap() != 0 {
+                        return Err(ToValuesError::InvalidValue(Anon));
+                    }
+                    info.value
+                }
+                Some(Entry::Block(block)) => match block.to_values(x, &limits) {
+                    // the block must fill its length
+                    Ok((values, end)) if end == x.len() => Val::Fields(values),
+                    Ok(_) => return Err(ToValuesError::InvalidValue(Anon)),
+                    Err(e) => {
+                        return Err(ToValuesError::Nested {
+                            name: Anon,
+                            ty,
+                            error: Box::new(e),
+                        })
+                    }
+                },
+                None => Val::Bytes(x),
+            };
+            items.push(TlvItem { ty, value });
+        }
+        Ok(items)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::{Len, StrLen, U64};
+
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let block = get_block();
+
+        let items = vec![
+            TlvItem {
+                ty: 0x40,
+                value: Val::Bytes(&[0xaa, 0xbb]),
+            },
+            TlvItem {
+                ty: 1,
+                value: Val::Str("host"),
+            },
+            TlvItem {
+                ty: 2,
+                value: Val::Fields(vec![Val::VarInt(7), Val::Bytes(&[1])]),
+            },
+        ];
+        let mut values = HashMap::new();
+        values.insert(Name::Version, Val::VarInt(1));
+        values.insert(Name::Extensions, Val::Tlv(items.clone()));
+
+        let mut buf = vec![0; 1024];
+        let end = block.to_bytes(&values, &mut buf).unwrap();
+        buf.truncate(end);
+        assert_eq!(
+            buf,
+            [
+                1, // version
+                0x40, 0x40, 2, 0xaa, 0xbb, // unknown
+                1, 4, b'h', b'o', b's', b't', // str
+                2, 3, 7, 1, 1, // block
+            ]
+        );
+
+        let mut decoded = HashMap::new();
+        block.to_values(&buf, &mut decoded).unwrap();
+        assert_eq!(decoded[&Name::Extensions].value.tlv().unwrap(), &items);
+
+        let mut reencoded = vec![0; 1024];
+        let mut values = HashMap::new();
+        values.insert(Name::Version, Val::VarInt(1));
+        values.insert(Name::Extensions, decoded[&Name::Extensions].value.clone());
+        let end = block.to_bytes(&values, &mut reencoded).unwrap();
+        assert_eq!(&reencoded[..end], &buf);
+    }
+
+    #[test]
+    fn test_known_types() {
+        let mut tlv = Tlv::new(TlvInt::U8, TlvInt::U8);
+        tlv.add_def(3, Def::VarInt(U64::Var));
+        let mut block = Block::new();
+        block.add_field(Name::Version, Def::VarInt(U64::Var));
+        tlv.add_block(1, block);
+
+        assert_eq!(tlv.known_types().collect::<Vec<_>>(), [3, 1]);
+        assert!(matches!(tlv.def(3), Some(Def::VarInt(U64::Var))));
+        assert!(tlv.def(1).is_none());
+        assert!(tlv.def(2).is_none());
+        assert!(matches!(
+            tlv.entry(3),
+            Some(TlvEntry::Def(Def::VarInt(U64::Var)))
+        ));
+        match tlv.entry(1) {
+            Some(TlvEntry::Block(fields)) => {
+                assert_eq!(fields.len(), 1);
+                assert_eq!(fields[0].0, "Version");
+                assert!(matches!(fields[0].1, Def::VarInt(U64::Var)));
+            }
+            _ => panic!("expected a block"),
+        }
+        assert!(tlv.entry(2).is_none());
+    }
+
+    #[test]
+    fn test_int() {
+        let mut tlv = Tlv::new(TlvInt::U8, TlvInt::U16);
+        tlv.set_prefix(TlvInt::U16)
+            .add_def(1, Def::VarInt(U64::Var));
+        let mut block = Block::new();
+        block.add_field(Name::Extensions, Def::Tlv(tlv));
+        block.add_field(Name::Version, Def::VarInt(U64::Var));
+
+        let items = vec![TlvItem {
+            ty: 1,
+            value: Val::VarInt(2),
+        }];
+        let mut values = HashMap::new();
+        values.insert(Name::Extensions, Val::Tlv(items.clone()));
+        values.insert(Name::Version, Val::VarInt(3));
+        let mut buf = vec![0; 1024];
+        let end = block.to_bytes(&values, &mut buf).unwrap();
+        buf.truncate(end);
+        assert_eq!(buf, [0, 4, 1, 0, 1, 2, 3]);
+
+        let mut decoded = HashMap::new();
+        block.to_values(&buf, &mut decoded).unwrap();
+        let info = &decoded[&Name::Extensions];
+        assert_eq!(info.value, Val::Tlv(items));
+        assert_eq!(info.prefix, Some(crate::Span { pos: 0, len: 2 }));
+        assert_eq!(decoded[&Name::Version].value, Val::VarInt(3));
+        let mut view = block.view(&buf);
+        assert_eq!(view.get(&Name::Version).unwrap().unwrap().pos, 6);
+
+        let items = vec![TlvItem {
+            ty: 0x100,
+            value: Val::Bytes(&[]),
+        }];
+        values.insert(Name::Extensions, Val::Tlv(items));
+        let e = block.to_bytes(&values, &mut buf).unwrap_err();
+        assert_eq!(e, ToBytesError::OutOfRange(Name::Extensions));
+        assert_eq!(
+            Tlv::new(TlvInt::U8, TlvInt::U8)
+                .set_prefix(TlvInt::U8)
+                .prefix(),
+            Some(TlvInt::U8)
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_no_prefix_not_last() {
+        let mut block = Block::new();
+        block.add_field(Name::Extensions, Def::Tlv(Tlv::new(TlvInt::U8, TlvInt::U8)));
+        block.add_field(Name::Version, Def::VarInt(U64::Var));
+    }
+
+    #[test]
+    fn test_invalid() {
+        let block = get_block();
+        let mut decoded = HashMap::new();
+
+        // the length runs past the end
+        let e = block.to_values(&[1, 0x05, 3, 1], &mut decoded).unwrap_err();
+        assert_eq!(e, ToValuesError::NotEnoughData(Name::Extensions));
+        // the varint does not fill its length
+        let e = block
+            .to_values(&[1, 1, 5, b'a', b'b', 0, 0, 0], &mut decoded)
+            .unwrap_err();
+        assert_eq!(e, ToValuesError::InvalidValue(Name::Extensions));
+        // a known type with a value of the wrong type
+        let mut values = HashMap::new();
+        values.insert(Name::Version, Val::VarInt(1));
+        let items = vec![TlvItem {
+            ty: 1,
+            value: Val::VarInt(2),
+        }];
+        values.insert(Name::Extensions, Val::Tlv(items));
+        let e = block.to_bytes(&values, &mut [0; 64]).unwrap_err();
+        assert_eq!(e, ToBytesError::InvalidValue(Name::Extensions));
+    }
+
+    #[test]
+    fn test_long_values() {
+        let block = get_block();
+        let payload = vec![0xab; 300];
+        let items = vec![
+            TlvItem {
+                ty: 2,
+                value: Val::Fields(vec![Val::VarInt(7), Val::Bytes(&payload)]),
+            },
+            TlvItem {
+                ty: 3,
+                value: Val::Bytes(&payload),
+            },
+        ];
+        let mut values = HashMap::new();
+        values.insert(Name::Version, Val::VarInt(1));
+        values.insert(Name::Extensions, Val::Tlv(items.clone()));
+
+        let mut buf = vec![0; 1024];
+        let end = block.to_bytes(&values, &mut buf).unwrap();
+        // the lengths of 303 and 300 bytes take two bytes each
+        assert_eq!(end, 1 + (1 + 2 + 303) + (1 + 2 + 300));
+        assert_eq!(&buf[1..4], &[2, 0x41, 0x2f]);
+
+        let mut decoded = HashMap::new();
+        block.to_values(&buf[..end], &mut decoded).unwrap();
+        assert_eq!(decoded[&Name::Extensions].value.tlv().unwrap(), &items);
+
+        let e = block.to_bytes(&values, &mut buf[..end - 1]).unwrap_err();
+        assert_eq!(e, ToBytesError::NotEnoughSpace(Name::Extensions));
+
+        // a length of 300 does not fit a single byte
+        let mut block = Block::new();
+        block.add_field(Name::Extensions, Def::Tlv(Tlv::new(TlvInt::U8, TlvInt::U8)));
+        let items = vec![TlvItem {
+            ty: 3,
+            value: Val::Bytes(&payload),
+        }];
+        values.insert(Name::Extensions, Val::Tlv(items));
+        let e = block.to_bytes(&values, &mut buf).unwrap_err();
+        assert_eq!(e, ToBytesError::OutOfRange(Name::Extensions));
+    }
+
+    #[test]
+    fn test_nested_errors() {
+        let block = get_block();
+
+        let mut values = HashMap::new();
+        values.insert(Name::Version, Val::VarInt(1));
+        let items = vec![TlvItem {
+            ty: 2,
+            value: Val::Fields(vec![Val::VarInt(7)]),
+        }];
+        values.insert(Name::Extensions, Val::Tlv(items));
+        let e = block.to_bytes(&values, &mut [0; 64]).unwrap_err();
+        assert_eq!(
+            e,
+            ToBytesError::Nested {
+                name: Name::Extensions,
+                ty: 2,
+                error: Box::new(ToBytesError::NoValueProvided("Payload".to_string())),
+            }
+        );
+
+        let mut decoded = HashMap::new();
+        let e = block.to_values(&[1, 2, 2, 7, 5], &mut decoded).unwrap_err();
+        assert_eq!(
+            e,
+            ToValuesError::Nested {
+                name: Name::Extensions,
+                ty: 2,
+                error: Box::new(ToValuesError::NotEnoughData("Payload".to_string())),
+            }
+        );
+    }
+
+    #[test]
+    fn test_nested_limits() {
+        let mut nested = Block::new();
+        nested.add_field(Name::Payload, Def::Bytes(Len::Var));
+        nested.set_limits(Limits {
+            max_len: Some(2),
+            ..Default::default()
+        });
+        let mut tlv = Tlv::new(TlvInt::Varint, TlvInt::Varint);
+        tlv.add_block(2, nested);
+        let mut block = Block::new();
+        block.add_field(Name::Extensions, Def::Tlv(tlv));
+
+        let mut decoded = HashMap::new();
+        block.to_values(&[2, 3, 2, 1, 2], &mut decoded).unwrap();
+        let e = block
+            .to_values(&[2, 4, 3, 1, 2, 3], &mut decoded)
+            .unwrap_err();
+        assert_eq!(
+            e,
+            ToValuesError::Nested {
+                name: Name::Extensions,
+                ty: 2,
+                error: Box::new(ToValuesError::LimitExceeded("Payload".to_string())),
+            }
+        );
+    }
+
+    #[test]
+    fn test_limits() {
+        let mut inner = Tlv::new(TlvInt::Varint, TlvInt::Varint);
+        inner.add_def(1, Def::VarInt(U64::Var));
+        let mut outer = Tlv::new(TlvInt::Varint, TlvInt::Varint);
+        outer.add_def(1, Def::Tlv(inner));
+        let mut block = Block::new();
+        block.add_field(Name::Extensions, Def::Tlv(outer));
+
+        let buf = [1, 5, 1, 1, 5, 9, 0, 7, 1, 1];
+        let mut decoded = HashMap::new();
+        block.to_values(&buf, &mut decoded).unwrap();
+        let items = decoded[&Name::Extensions].value.tlv().unwrap();
+        assert_eq!(items.len(), 2);
+        assert_eq!(
+            items[0].value,
+            Val::Tlv(vec![
+                TlvItem {
+                    ty: 1,
+                    value: Val::VarInt(5)
+                },
+                TlvItem {
+                    ty: 9,
+                    value: Val::Bytes(&[])
+                },
+            ])
+        );
+
+        block.set_limits(Limits {
+            max_depth: Some(1),
+            ..Default::default()
+        });
+        let e = block.to_values(&buf, &mut decoded).unwrap_err();
+        assert_eq!(e, ToValuesError::LimitExceeded(Name::Extensions));
+
+        block.set_limits(Limits {
+            max_count: Some(1),
+            ..Default::default()
+        });
+        let e = block.to_values(&buf, &mut decoded).unwrap_err();
+        assert_eq!(e, ToValuesError::LimitExceeded(Name::Extensions));
+
+        block.set_limits(Limits {
+            max_depth: Some(2),
+            max_count: Some(2),
+            ..Default::default()
+        });
+        block.to_values(&buf, &mut decoded).unwrap();
+    }
+
+    fn get_block() -> Block<Name> {
+        let mut nested = Block::new();
+        nested.add_field(Name::Version, Def::VarInt(U64::Var));
+        nested.add_field(Name::Payload, Def::Bytes(Len::Var));
+
+        let mut tlv = Tlv::new(TlvInt::Varint, TlvInt::Varint);
+        tlv.add_def(1, Def::Str(StrLen::Padded { len: 4, pad: 0 }));
+        tlv.add_block(2, nested);
+
+        let mut block = Block::new();
+        block.add_field(Name::Version, Def::VarInt(U64::Var));
+        block.add_field(Name::Extensions, Def::Tlv(tlv));
+        block
+    }
+
+    #[derive(Debug, PartialEq, Eq, Hash, Clone)]
+    enum Name {
+        Version,
+        Payload,
+        Extensions,
+    }
+
+    impl FieldName for Name {}
+}
@@ -1,13 +1,37 @@
-#[derive(Debug)]
+use std::{
+    borrow::Cow,
+    fmt,
+    net::{Ipv4Addr, Ipv6Addr},
+    ops::Range,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::TlvItem;
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum Val<'buf> {
     VarInt(u64),
     Bytes(&'buf [u8]),
+    Str(&'buf str),
+    /// An integer with its label from [`Labels`](crate::Labels)
+    Enum(u64, Cow<'static, str>),
+    F32(f32),
+    F64(f64),
+    Bool(bool),
+    Ipv4(Ipv4Addr),
+    Ipv6(Ipv6Addr),
+    Mac([u8; 6]),
+    Uuid([u8; 16]),
+    Time(SystemTime),
+    Tlv(Vec<TlvItem<'buf>>),
+    /// The values of a nested block in field order
+    Fields(Vec<Val<'buf>>),
 }
 
 impl<'buf> Val<'buf> {
     pub fn varint(&self) -> Result<u64, Error> {
         match self {
-            Val::VarInt(x) => Ok(*x),
+            Val::VarInt(x) | Val::Enum(x, _) => Ok(*x),
             _ => Err(Error::InvalidType),
         }
     }
@@ -18,6 +42,230 @@ impl<'buf> Val<'buf> {
             _ => Err(Error::InvalidType),
         }
     }
+
+    pub fn str(&self) -> Result<&str, Error> {
+        match self {
+            Val::Str(x) => Ok(x),
+            _ => Err(Error::InvalidType),
+        }
+    }
+
+    pub fn f32(&self) -> Result<f32, Error> {
+        match self {
+            Val::F32(x) => Ok(*x),
+            _ => Err(Error::InvalidType),
+        }
+    }
+
+    /// Returns a float of either precision.
+    pub fn f64(&self) -> Result<f64, Error> {
+        match self {
+            Val::F32(x) => Ok(*x as f64),
+            Val::F64(x) => Ok(*x),
+            _ => Err(Error::InvalidType),
+        }
+    }
+
+    pub fn bool(&self) -> Result<bool, Error> {
+        match self {
+            Val::Bool(x) => Ok(*x),
+            _ => Err(Error::InvalidType),
+        }
+    }
+
+    pub fn ipv4(&self) -> Result<Ipv4Addr, Error> {
+        match self {
+            Val::Ipv4(x) => Ok(*x),
+            _ => Err(Error::InvalidType),
+        }
+    }
+
+    pub fn ipv6(&self) -> Result<Ipv6Addr, Error> {
+        match self {
+            Val::Ipv6(x) => Ok(*x),
+            _ => Err(Error::InvalidType),
+        }
+    }
+
+    pub fn mac(&self) -> Result<[u8; 6], Error> {
+        match self {
+            Val::Mac(x) => Ok(*x),
+            _ => Err(Error::InvalidType),
+        }
+    }
+
+    pub fn uuid(&self) -> Result<[u8; 16], Error> {
+        match self {
+            Val::Uuid(x) => Ok(*x),
+            _ => Err(Error::InvalidType),
+        }
+    }
+
+    pub fn time(&self) -> Result<SystemTime, Error> {
+        match self {
+            Val::Time(x) => Ok(*x),
+            _ => Err(Error::InvalidType),
+        }
+    }
+
+    pub fn tlv(&self) -> Result<&[TlvItem<'buf>], Error> {
+        match self {
+            Val::Tlv(x) => Ok(x),
+            _ => Err(Error::InvalidType),
+        }
+    }
+
+    pub fn fields(&self) -> Result<&[Val<'buf>], Error> {
+        match self {
+            Val::Fields(x) => Ok(x),
+            _ => Err(Error::InvalidType),
+        }
+    }
+
+    /// Returns the label of an integer that has one.
+    pub fn label(&self) -> Result<&str, Error> {
+        match self {
+            Val::Enum(_, x) => Ok(x),
+            _ => Err(Error::InvalidType),
+        }
+    }
+}
+
+impl fmt::Display for Val<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Val::VarInt(x) => write!(f, "{x}"),
+            Val::Bytes(x) => {
+                for byte in x.iter() {
+                    write!(f, "{byte:02x}")?;
+                }
+                Ok(())
+            }
+            Val::Str(x) => write!(f, "{x:?}"),
+            Val::Enum(x, label) => write!(f, "{label} ({x})"),
+            Val::F32(x) => write!(f, "{x}"),
+            Val::F64(x) => write!(f, "{x}"),
+            Val::Bool(x) => write!(f, "{x}"),
+            Val::Ipv4(x) => write!(f, "{x}"),
+            Val::Ipv6(x) => write!(f, "{x}"),
+            Val::Mac(x) => {
+                let x = x.map(|x| format!("{x:02x}"));
+                write!(f, "{}", x.join(":"))
+            }
+            Val::Uuid(x) => {
+                for (i, byte) in x.iter().enumerate() {
+                    if matches!(i, 4 | 6 | 8 | 10) {
+                        write!(f, "-")?;
+                    }
+                    write!(f, "{byte:02x}")?;
+                }
+                Ok(())
+            }
+            // seconds since 1970
+            Val::Time(x) => match x.duration_since(UNIX_EPOCH) {
+                Ok(d) => write!(f, "{}.{:09}", d.as_secs(), d.subsec_nanos()),
+                Err(e) => {
+                    let d = e.duration();
+                    write!(f, "-{}.{:09}", d.as_secs(), d.subsec_nanos())
+                }
+            },
+            Val::Tlv(x) => {
+                write!(f, "{{")?;
+                for (i, item) in x.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}: {}", item.ty, item.value)?;
+                }
+                write!(f, "}}")
+            }
+            Val::Fields(x) => {
+                write!(f, "(")?;
+                for (i, value) in x.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{value}")?;
+                }
+                write!(f, ")")
+            }
+        }
+    }
+}
+
+/// A [`Val`] that owns its bytes and strings, such as one read from JSON.
+#[derive(Debug, Clone, PartialEq)]
+pub enum OwnedVal {
+    VarInt(u64),
+    Bytes(Vec<u8>),
+    Str(String),
+    Enum(u64, Cow<'static, str>),
+    F32(f32),
+    F64(f64),
+    Bool(bool),
+    Ipv4(Ipv4Addr),
+    Ipv6(Ipv6Addr),
+    Mac([u8; 6]),
+    Uuid([u8; 16]),
+    Time(SystemTime),
+    /// Items as types and values
+    Tlv(Vec<(u64, OwnedVal)>),
+    Fields(Vec<OwnedVal>),
+}
+
+impl OwnedVal {
+    /// Returns the value borrowing from `self`, ready to be encoded.
+    #[must_use]
+    pub fn as_val(&self) -> Val<'_> {
+        match self {
+            OwnedVal::VarInt(x) => Val::VarInt(*x),
+            OwnedVal::Bytes(x) => Val::Bytes(x),
+            OwnedVal::Str(x) => Val::Str(x),
+            OwnedVal::Enum(x, label) => Val::Enum(*x, label.clone()),
+            OwnedVal::F32(x) => Val::F32(*x),
+            OwnedVal::F64(x) => Val::F64(*x),
+            OwnedVal::Bool(x) => Val::Bool(*x),
+            OwnedVal::Ipv4(x) => Val::Ipv4(*x),
+            OwnedVal::Ipv6(x) => Val::Ipv6(*x),
+            OwnedVal::Mac(x) => Val::Mac(*x),
+            OwnedVal::Uuid(x) => Val::Uuid(*x),
+            OwnedVal::Time(x) => Val::Time(*x),
+            OwnedVal::Tlv(x) => Val::Tlv(
+                x.iter()
+                    .map(|(ty, value)| TlvItem {
+                        ty: *ty,
+                        value: value.as_val(),
+                    })
+                    .collect(),
+            ),
+            OwnedVal::Fields(x) => Val::Fields(x.iter().map(OwnedVal::as_val).collect()),
+        }
+    }
+}
+
+impl From<&Val<'_>> for OwnedVal {
+    fn from(value: &Val<'_>) -> Self {
+        match value {
+            Val::VarInt(x) => OwnedVal::VarInt(*x),
+            Val::Bytes(x) => OwnedVal::Bytes(x.to_vec()),
+            Val::Str(x) => OwnedVal::Str(x.to_string()),
+            Val::Enum(x, label) => OwnedVal::Enum(*x, label.clone()),
+            Val::F32(x) => OwnedVal::F32(*x),
+            Val::F64(x) => OwnedVal::F64(*x),
+            Val::Bool(x) => OwnedVal::Bool(*x),
+            Val::Ipv4(x) => OwnedVal::Ipv4(*x),
+            Val::Ipv6(x) => OwnedVal::Ipv6(*x),
+            Val::Mac(x) => OwnedVal::Mac(*x),
+            Val::Uuid(x) => OwnedVal::Uuid(*x),
+            Val::Time(x) => OwnedVal::Time(*x),
+            Val::Tlv(x) => OwnedVal::Tlv(
+                x.iter()
+                    .map(|item| (item.ty, OwnedVal::from(&item.value)))
+                    .collect(),
+            ),
+            Val::Fields(x) => OwnedVal::Fields(x.iter().map(OwnedVal::from).collect()),
+        }
+    }
 }
 
 #[derive(Debug, PartialEq)]
@@ -25,10 +273,50 @@ pub enum Error {
     InvalidType,
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 pub struct ValInfo<'buf> {
     pub value: Val<'buf>,
     pub pos: usize,
+    /// The whole encoded field, including its length prefix if any
+    pub span: Span,
+    /// The varint length prefix of a [`Len::Var`](crate::Len::Var) field
+    pub prefix: Option<Span>,
+    /// The value through the [`Scale`](crate::Scale) of the field, if any
+    pub physical: Option<f64>,
+}
+
+impl<'buf> ValInfo<'buf> {
+    /// Returns the encoded field without its length prefix.
+    #[must_use]
+    pub fn payload(&self) -> Span {
+        match self.prefix {
+            Some(prefix) => Span {
+                pos: prefix.end(),
+                len: self.span.len - prefix.len,
+            },
+            None => self.span,
+        }
+    }
+}
+
+/// A region of an encoded buffer.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Span {
+    pub pos: usize,
+    pub len: usize,
+}
+
+impl Span {
+    #[must_use]
+    pub fn end(&self) -> usize {
+        self.pos + self.len
+    }
+
+    #[must_use]
+    pub fn range(&self) -> Range<usize> {
+        self.pos..self.end()
+    }
 }
 
 #[cfg(test)]
@@ -46,5 +334,109 @@ mod tests {
 
         let val = Val::VarInt(0x1234);
         assert_eq!(val.bytes().unwrap_err(), Error::InvalidType);
+
+        let val = Val::Str("abc");
+        assert_eq!(val.str().unwrap(), "abc");
+        assert_eq!(val.bytes().unwrap_err(), Error::InvalidType);
+
+        let val = Val::Enum(1, "Ping".into());
+        assert_eq!(val.varint().unwrap(), 1);
+        assert_eq!(val.label().unwrap(), "Ping");
+        assert_eq!(Val::VarInt(1).label().unwrap_err(), Error::InvalidType);
+
+        let val = Val::F32(1.5);
+        assert_eq!(val.f32().unwrap(), 1.5);
+        assert_eq!(val.f64().unwrap(), 1.5);
+        assert_eq!(Val::F64(1.5).f32().unwrap_err(), Error::InvalidType);
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(Val::VarInt(0x1234).to_string(), "4660");
+        assert_eq!(Val::Bytes(&[0x0a, 0xff]).to_string(), "0aff");
+        assert_eq!(Val::Str("a\"b").to_string(), "\"a\\\"b\"");
+        assert_eq!(Val::Enum(1, "Ping".into()).to_string(), "Ping (1)");
+        assert_eq!(
+            Val::Ipv4(Ipv4Addr::new(192, 0, 2, 1)).to_string(),
+            "192.0.2.1"
+        );
+        assert_eq!(
+            Val::Mac([0, 0x1b, 0x63, 0x84, 0x45, 0xe6]).to_string(),
+            "00:1b:63:84:45:e6"
+        );
+        let uuid = [
+            0x12, 0x3e, 0x45, 0x67, 0xe8, 0x9b, 0x12, 0xd3, 0xa4, 0x56, 0x42, 0x66, 0x14, 0x17,
+            0x40, 0x00,
+        ];
+        assert_eq!(
+            Val::Uuid(uuid).to_string(),
+            "123e4567-e89b-12d3-a456-426614174000"
+        );
+        let time = UNIX_EPOCH + std::time::Duration::from_millis(1500);
+        assert_eq!(Val::Time(time).to_string(), "1.500000000");
+        let items = vec![
+            TlvItem {
+                ty: 1,
+                value: Val::Fields(vec![Val::VarInt(2), Val::Str("a")]),
+            },
+            TlvItem {
+                ty: 3,
+                value: Val::Bytes(&[4]),
+            },
+        ];
+        assert_eq!(Val::Tlv(items).to_string(), "{1: (2, \"a\"), 3: 04}");
+    }
+
+    #[test]
+    fn test_payload() {
+        let vec = vec![1, 2, 3];
+        let info = ValInfo {
+            value: Val::Bytes(&vec),
+            pos: 4,
+            span: Span { pos: 4, len: 4 },
+            prefix: Some(Span { pos: 4, len: 1 }),
+            physical: None,
+        };
+        assert_eq!(info.payload(), Span { pos: 5, len: 3 });
+        assert_eq!(info.payload().range(), 5..8);
+
+        let info = ValInfo {
+            value: Val::Bytes(&vec),
+            pos: 4,
+            span: Span { pos: 4, len: 3 },
+            prefix: None,
+            physical: None,
+        };
+        assert_eq!(info.payload(), info.span);
+    }
+
+    #[test]
+    fn test_owned() {
+        let items = vec![
+            TlvItem {
+                ty: 1,
+                value: Val::Fields(vec![Val::Str("a"), Val::Enum(2, "Two".into())]),
+            },
+            TlvItem {
+                ty: 3,
+                value: Val::Bytes(&[4]),
+            },
+        ];
+        let val = Val::Tlv(items);
+        let owned = OwnedVal::from(&val);
+        assert_eq!(
+            owned,
+            OwnedVal::Tlv(vec![
+                (
+                    1,
+                    OwnedVal::Fields(vec![
+                        OwnedVal::Str("a".to_string()),
+                        OwnedVal::Enum(2, "Two".into())
+                    ])
+                ),
+                (3, OwnedVal::Bytes(vec![4])),
+            ])
+        );
+        assert_eq!(owned.as_val(), val);
     }
 }
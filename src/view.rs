@@ -0,0 +1,220 @@
+use octets::Octets;
+
+use crate::{block::fixed_size_at, Block, FieldName, ToValuesError, ValInfo};
+
+/// A lazily decoded view over an encoded buffer.
+///
+/// Fields are only decoded when asked for by name. The offsets of fields
+/// walked past on the way are cached, so later lookups do not walk the buffer
+/// again. Offsets of fields preceded only by fixed-size fields are computed
+/// directly and never touch the cache, so viewing a fixed-layout block does
+/// not allocate.
+///
+/// Fields that are skipped over are not validated, and checksums are not
+/// verified as they cover the whole block.
+pub struct BlockView<'block, 'buf, F>
+where
+    F: FieldName,
+{
+    block: &'block Block<F>,
+    buf: &'buf [u8],
+    /// Start offsets of fields walked past so far, indexed by field position
+    offsets: Vec<usize>,
+}
+
+impl<'block, 'buf, F> BlockView<'block, 'buf, F>
+where
+    F: FieldName,
+{
+    #[must_use]
+    pub fn new(block: &'block Block<F>, buf: &'buf [u8]) -> Self {
+        BlockView {
+            block,
+            buf,
+            offsets: Vec::new(),
+        }
+    }
+
+    /// Decodes the field named `name`.
+    ///
+    /// Returns `None` if the block has no such field or the field has no
+    /// value as per [`Def::has_value`](crate::Def::has_value).
+    pub fn get(&mut self, name: &F) -> Result<Option<ValInfo<'buf>>, ToValuesError<F>> {
+        self.block.check_trailer()?;
+        let index = match self.block.position(name) {
+            Some(index) => index,
+            None => return Ok(None),
+        };
+        let field = &self.block.fields()[index];
+        if !field.def().has_value() {
+            return Ok(None);
+        }
+        let pos = self.offset(index)?;
+        let mut b = Octets::with_slice(self.buf);
+        if b.skip(pos).is_err() {
+            return Err(ToValuesError::NotEnoughData(field.name().clone()));
+        }
+        let value = field.to_value_(&mut b, self.block.limits())?;
+        self.block.check_size(field, b.off())?;
+        Ok(Some(value))
+    }
+
+    /// Returns the start offset of the field at `index`.
+    fn offset(&mut self, index: usize) -> Result<usize, ToValuesError<F>> {
+        let mut pos = 0;
+        for field in &self.block.fields()[..index] {
+            match fixed_size_at(field.def(), pos) {
+                Some(size)
+                    if field
+                        .def()
+                        .has_fixed_size_in(self.buf.get(pos..).unwrap_or(&[])) =>
+                {
+                    pos += size
+                }
+                _ => return self.walk(index),
+            }
+        }
+        Ok(pos)
+    }
+
+    /// Walks the buffer from the furthest known field up to the field at `index`.
+    fn walk(&mut self, index: usize) -> Result<usize, ToValuesError<F>> {
+        let fields = self.block.fields();
+        if self.offsets.is_empty() {
+            self.offsets.push(0);
+        }
+        while self.offsets.len() <= index {
+            let i = self.offsets.len() - 1;
+            let mut b = Octets::with_slice(self.buf);
+            if b.skip(self.offsets[i]).is_err() {
+                return Err(ToValuesError::NotEnoughData(fields[i].name().clone()));
+            }
+            fields[i].skip_(&mut b, self.block.limits())?;
+            self.block.check_size(&fields[i], b.off())?;
+            self.offsets.push(b.off());
+        }
+        Ok(self.offsets[index])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::{Def, Len, U64};
+
+    use super::*;
+
+    #[test]
+    fn test_get() {
+        let block = get_block();
+        let buf = vec![
+            // fixed varint
+            0x41,
+            0x02,
+            // varint
+            0x12 | 0x40,
+            0x34,
+            // bytes with var len
+            3,
+            1,
+            2,
+            3,
+            // bytes with fixed len
+            9,
+        ];
+        let mut view = block.view(&buf);
+
+        let ValInfo { value, pos, .. } = view.get(&Name::BytesFixedLen).unwrap().unwrap();
+        assert_eq!(value.bytes().unwrap(), vec![9]);
+        assert_eq!(pos, 8);
+        assert_eq!(view.offsets, vec![0, 2, 4, 8]);
+
+        let ValInfo { value, pos, .. } = view.get(&Name::VarInt).unwrap().unwrap();
+        assert_eq!(value.varint().unwrap(), 0x1234);
+        assert_eq!(pos, 2);
+
+        let ValInfo { value, pos, .. } = view.get(&Name::BytesVarLen).unwrap().unwrap();
+        assert_eq!(value.bytes().unwrap(), vec![1, 2, 3]);
+        assert_eq!(pos, 4);
+
+        assert!(view.get(&Name::Absent).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_fixed_layout() {
+        let mut block = Block::new();
+        block.add_field(Name::FixedVarInt, Def::VarInt(U64::Fixed(0x0102)));
+        block.add_field(Name::BytesFixedLen, Def::Bytes(Len::Fixed(2)));
+        let buf = vec![0x41, 0x02, 7, 8];
+        let mut view = block.view(&buf);
+
+        let ValInfo { value, pos, .. } = view.get(&Name::BytesFixedLen).unwrap().unwrap();
+        assert_eq!(value.bytes().unwrap(), vec![7, 8]);
+        assert_eq!(pos, 2);
+        assert_eq!(view.offsets.capacity(), 0);
+    }
+
+    #[test]
+    fn test_align() {
+        let mut block = Block::new();
+        block.add_field(Name::FixedVarInt, Def::VarInt(U64::Fixed(5)));
+        block.add_field(Name::Align, Def::Align { to: 4, fill: 0 });
+        block.add_field(Name::BytesFixedLen, Def::Bytes(Len::Fixed(2)));
+        let buf = vec![5, 0, 0, 0, 7, 8];
+        let mut view = block.view(&buf);
+
+        let ValInfo { value, pos, .. } = view.get(&Name::BytesFixedLen).unwrap().unwrap();
+        assert_eq!(value.bytes().unwrap(), vec![7, 8]);
+        assert_eq!(pos, 4);
+        assert_eq!(view.offsets.capacity(), 0);
+    }
+
+    #[test]
+    fn test_non_minimal_constant() {
+        let mut block = Block::new();
+        block.add_field(Name::FixedVarInt, Def::VarInt(U64::Fixed(5)));
+        block.add_field(Name::BytesFixedLen, Def::Bytes(Len::Fixed(1)));
+        let buf = vec![0x40, 5, 7];
+        let mut view = block.view(&buf);
+
+        let ValInfo { value, pos, .. } = view.get(&Name::BytesFixedLen).unwrap().unwrap();
+        assert_eq!(value.bytes().unwrap(), vec![7]);
+        assert_eq!(pos, 2);
+
+        let mut values = HashMap::new();
+        block.to_values(&buf, &mut values).unwrap();
+        assert_eq!(values[&Name::BytesFixedLen].value, value);
+    }
+
+    #[test]
+    fn test_not_enough_data() {
+        let block = get_block();
+        let buf = vec![0x41, 0x02, 0x12 | 0x40, 0x34, 3, 1];
+        let mut view = block.view(&buf);
+
+        let e = view.get(&Name::BytesFixedLen).unwrap_err();
+        assert_eq!(e, ToValuesError::NotEnoughData(Name::BytesVarLen));
+    }
+
+    fn get_block() -> Block<Name> {
+        let mut block = Block::new();
+        block.add_field(Name::FixedVarInt, Def::VarInt(U64::Fixed(0x0102)));
+        block.add_field(Name::VarInt, Def::VarInt(U64::Var));
+        block.add_field(Name::BytesVarLen, Def::Bytes(Len::Var));
+        block.add_field(Name::BytesFixedLen, Def::Bytes(Len::Fixed(1)));
+        block
+    }
+
+    #[derive(Debug, PartialEq, Eq, Hash, Clone)]
+    enum Name {
+        FixedVarInt,
+        VarInt,
+        BytesVarLen,
+        BytesFixedLen,
+        Absent,
+        Align,
+    }
+
+    impl FieldName for Name {}
+}